@@ -41,6 +41,15 @@ impl CfgOptions {
         self.enabled.insert(CfgAtom::KeyValue { key, value });
     }
 
+    /// Returns the values the key-value option `key` is set to, e.g. the
+    /// enabled features for `feature`.
+    pub fn get_cfg_values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a SmolStr> + 'a {
+        self.enabled.iter().filter_map(move |atom| match atom {
+            CfgAtom::KeyValue { key: k, value } if k == key => Some(value),
+            _ => None,
+        })
+    }
+
     pub fn apply_diff(&mut self, diff: CfgDiff) {
         for atom in diff.enable {
             self.enabled.insert(atom);
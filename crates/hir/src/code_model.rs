@@ -33,7 +33,7 @@ use hir_ty::{
     traits::{FnTrait, Solution, SolutionVariables},
     AliasTy, BoundVar, CallableDefId, CallableSig, Canonical, DebruijnIndex, GenericPredicate,
     InEnvironment, Mutability, Obligation, ProjectionPredicate, ProjectionTy, Scalar, Substs,
-    TraitEnvironment, Ty, TyDefId, TyVariableKind,
+    TraitEnvironment, Ty, TyDefId, TyVariableKind, TypeWalk, VariableKind,
 };
 use rustc_hash::FxHashSet;
 use stdx::{format_to, impl_from};
@@ -1568,8 +1568,13 @@ impl Type {
         }
     }
 
+    /// Peels off all references, see `remove_ref`.
+    pub fn strip_references(&self) -> Type {
+        self.derived(self.ty.value.strip_references().0.clone())
+    }
+
     pub fn is_unknown(&self) -> bool {
-        matches!(self.ty.value, Ty::Unknown)
+        self.ty.value.is_unknown()
     }
 
     /// Checks that particular type `ty` implements `std::future::Future`.
@@ -1658,7 +1663,7 @@ impl Type {
                 self.ty.environment.clone(),
                 Obligation::Projection(predicate),
             ),
-            kinds: Arc::new([TyVariableKind::General]),
+            kinds: Arc::new([VariableKind::Ty(TyVariableKind::General)]),
         };
 
         match db.trait_solve(self.krate, goal)? {
@@ -1691,11 +1696,11 @@ impl Type {
     }
 
     pub fn is_closure(&self) -> bool {
-        matches!(&self.ty.value, Ty::Closure { .. })
+        self.ty.value.as_closure().is_some()
     }
 
     pub fn is_fn(&self) -> bool {
-        matches!(&self.ty.value, Ty::FnDef(..) | Ty::Function { .. })
+        matches!(&self.ty.value, Ty::FnDef(..)) || self.ty.value.as_fn_ptr().is_some()
     }
 
     pub fn is_packed(&self, db: &dyn HirDatabase) -> bool {
@@ -1716,14 +1721,7 @@ impl Type {
     }
 
     pub fn contains_unknown(&self) -> bool {
-        return go(&self.ty.value);
-
-        fn go(ty: &Ty) -> bool {
-            match ty {
-                Ty::Unknown => true,
-                _ => ty.substs().map_or(false, |substs| substs.iter().any(go)),
-            }
-        }
+        self.ty.value.contains_unknown()
     }
 
     pub fn fields(&self, db: &dyn HirDatabase) -> Vec<(Field, Type)> {
@@ -1788,6 +1786,7 @@ impl Type {
         self.ty
             .value
             .strip_references()
+            .0
             .substs()
             .into_iter()
             .flat_map(|substs| substs.iter())
@@ -1925,7 +1924,7 @@ impl Type {
         }
 
         fn walk_type(db: &dyn HirDatabase, type_: &Type, cb: &mut impl FnMut(Type)) {
-            let ty = type_.ty.value.strip_references();
+            let ty = type_.ty.value.strip_references().0;
             match ty {
                 Ty::Adt(..) => {
                     cb(type_.derived(ty.clone()));
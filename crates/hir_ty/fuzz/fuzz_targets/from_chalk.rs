@@ -0,0 +1,11 @@
+//! Converts random Chalk types with `Ty::from_chalk`, see `hir_ty::fuzz`.
+
+#![no_main]
+use hir_ty::fuzz::FuzzDb;
+use libfuzzer_sys::fuzz_target;
+
+thread_local! {
+    static DB: FuzzDb = FuzzDb::default();
+}
+
+fuzz_target!(|data: &[u8]| DB.with(|db| db.check_from_chalk(data)));
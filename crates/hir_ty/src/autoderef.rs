@@ -14,7 +14,7 @@ use crate::{
     db::HirDatabase,
     traits::{InEnvironment, Solution},
     utils::generics,
-    BoundVar, Canonical, DebruijnIndex, Obligation, Substs, TraitRef, Ty,
+    BoundVar, Canonical, DebruijnIndex, Obligation, Substs, TraitRef, Ty, VariableKind,
 };
 
 const AUTODEREF_RECURSION_LIMIT: usize = 10;
@@ -91,7 +91,11 @@ fn deref_by_trait(
 
     let canonical = Canonical::new(
         in_env,
-        ty.value.kinds.iter().copied().chain(Some(chalk_ir::TyVariableKind::General)),
+        ty.value
+            .kinds
+            .iter()
+            .cloned()
+            .chain(Some(VariableKind::Ty(chalk_ir::TyVariableKind::General))),
     );
 
     let solution = db.trait_solve(krate, canonical)?;
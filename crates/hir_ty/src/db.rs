@@ -4,8 +4,8 @@ use std::sync::Arc;
 
 use base_db::{impl_intern_key, salsa, CrateId, Upcast};
 use hir_def::{
-    db::DefDatabase, expr::ExprId, ConstParamId, DefWithBodyId, FunctionId, GenericDefId, ImplId,
-    LocalFieldId, TypeParamId, VariantId,
+    db::DefDatabase, expr::ExprId, AdtId, ConstParamId, DefWithBodyId, FunctionId, GenericDefId,
+    ImplId, LocalFieldId, TypeAliasId, TypeParamId, VariantId,
 };
 use la_arena::ArenaMap;
 
@@ -13,7 +13,7 @@ use crate::{
     method_resolution::{InherentImpls, TraitImpls},
     traits::chalk,
     Binders, CallableDefId, GenericPredicate, InferenceResult, OpaqueTyId, PolyFnSig,
-    ReturnTypeImplTraits, TraitRef, Ty, TyDefId, ValueTyDefId,
+    ReturnTypeImplTraits, SolverConfig, TraitRef, Ty, TyDefId, ValueTyDefId, Variance,
 };
 use hir_expand::name::Name;
 
@@ -55,6 +55,12 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
         def: FunctionId,
     ) -> Option<Arc<Binders<ReturnTypeImplTraits>>>;
 
+    #[salsa::invoke(crate::lower::type_alias_impl_traits)]
+    fn type_alias_impl_traits(
+        &self,
+        def: TypeAliasId,
+    ) -> Option<Arc<Binders<ReturnTypeImplTraits>>>;
+
     #[salsa::invoke(crate::lower::generic_predicates_for_param_query)]
     #[salsa::cycle(crate::lower::generic_predicates_for_param_recover)]
     fn generic_predicates_for_param(
@@ -65,6 +71,11 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
     #[salsa::invoke(crate::lower::generic_predicates_query)]
     fn generic_predicates(&self, def: GenericDefId) -> Arc<[Binders<GenericPredicate>]>;
 
+    /// The variance of `def` in each of its type parameters.
+    #[salsa::invoke(crate::variance::adt_variances_query)]
+    #[salsa::cycle(crate::variance::adt_variances_recover)]
+    fn adt_variances(&self, def: AdtId) -> Arc<[Variance]>;
+
     #[salsa::invoke(crate::lower::generic_defaults_query)]
     fn generic_defaults(&self, def: GenericDefId) -> Arc<[Binders<Ty>]>;
 
@@ -99,6 +110,19 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
     #[salsa::invoke(crate::traits::chalk::impl_datum_query)]
     fn impl_datum(&self, krate: CrateId, impl_id: chalk::ImplId) -> Arc<chalk::ImplDatum>;
 
+    /// The trait ref of an impl's header, as Chalk sees it.
+    #[salsa::invoke(crate::traits::chalk::impl_trait_ref_chalk_query)]
+    fn impl_trait_ref_chalk(
+        &self,
+        impl_id: ImplId,
+    ) -> Option<Binders<chalk_ir::TraitRef<chalk::Interner>>>;
+
+    #[salsa::invoke(crate::traits::chalk::callable_sig_chalk_query)]
+    fn callable_sig_chalk(
+        &self,
+        def: CallableDefId,
+    ) -> Arc<Binders<chalk::FnDefInputsAndOutputDatum>>;
+
     #[salsa::invoke(crate::traits::chalk::fn_def_datum_query)]
     fn fn_def_datum(&self, krate: CrateId, fn_def_id: chalk::FnDefId) -> Arc<chalk::FnDefDatum>;
 
@@ -115,6 +139,18 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
         id: chalk::AssociatedTyValueId,
     ) -> Arc<chalk::AssociatedTyValue>;
 
+    #[salsa::invoke(crate::traits::chalk::dump_chalk_program_query)]
+    #[salsa::transparent]
+    fn dump_chalk_program(&self, krate: CrateId) -> String;
+
+    #[salsa::invoke(crate::traits::chalk::dump_impl_clause_query)]
+    #[salsa::transparent]
+    fn dump_impl_clause(&self, impl_id: ImplId) -> String;
+
+    /// The limits trait solving runs with.
+    #[salsa::input]
+    fn solver_config(&self) -> SolverConfig;
+
     #[salsa::invoke(crate::traits::trait_solve_query)]
     fn trait_solve(
         &self,
@@ -221,6 +221,9 @@ impl Diagnostic for MissingOkOrSomeInTailExpr {
     }
 }
 
+// Diagnostic: remove-this-semicolon
+//
+// This diagnostic is triggered when there's an erroneous `;` at the end of the block.
 #[derive(Debug)]
 pub struct RemoveThisSemicolon {
     pub file: HirFileId,
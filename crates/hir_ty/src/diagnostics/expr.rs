@@ -233,7 +233,7 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
                 args.insert(0, *receiver);
 
                 let receiver = &self.infer.type_of_expr[*receiver];
-                if receiver.strip_references().is_unknown() {
+                if receiver.strip_references().0.is_unknown() {
                     // if the receiver is of unknown type, it's very likely we
                     // don't know enough to correctly resolve the method call.
                     // This is kind of a band-aid for #6975.
@@ -294,7 +294,8 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         let match_expr_ty = match infer.type_of_expr.get(match_expr) {
             // If we can't resolve the type of the match expression
             // we cannot perform exhaustiveness checks.
-            None | Some(Ty::Unknown) => return,
+            None => return,
+            Some(ty) if ty.is_unknown() => return,
             Some(ty) => ty,
         };
 
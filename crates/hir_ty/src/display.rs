@@ -3,12 +3,14 @@
 use std::{borrow::Cow, fmt};
 
 use crate::{
-    db::HirDatabase, primitive, utils::generics, AliasTy, CallableDefId, CallableSig,
-    GenericPredicate, Lifetime, Obligation, OpaqueTy, OpaqueTyId, ProjectionTy, Scalar, Substs,
-    TraitRef, Ty,
+    db::HirDatabase,
+    primitive::{self, IntTy, UintTy},
+    utils::generics,
+    AliasTy, CallableDefId, CallableSig, GenericPredicate, Lifetime, Mutability, Obligation,
+    OpaqueTyId, ProjectionTy, Scalar, Substs, TraitRef, Ty,
 };
 use arrayvec::ArrayVec;
-use chalk_ir::Mutability;
+use base_db::CrateId;
 use hir_def::{
     db::DefDatabase, find_path, generics::TypeParamProvenance, item_scope::ItemInNs, AdtId,
     AssocContainerId, HasModule, Lookup, ModuleId, TraitId,
@@ -311,22 +313,15 @@ impl HirDisplay for Ty {
                     )?;
                 }
 
-                let datas;
                 let predicates = match t {
                     Ty::Dyn(predicates) if predicates.len() > 1 => {
                         Cow::Borrowed(predicates.as_ref())
                     }
-                    &Ty::Alias(AliasTy::Opaque(OpaqueTy {
-                        opaque_ty_id: OpaqueTyId::ReturnTypeImplTrait(func, idx),
-                        ref parameters,
-                    })) => {
-                        datas =
-                            f.db.return_type_impl_traits(func).expect("impl trait id without data");
-                        let data = (*datas)
-                            .as_ref()
-                            .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
-                        let bounds = data.subst(parameters);
-                        Cow::Owned(bounds.value)
+                    Ty::Alias(AliasTy::Opaque(opaque_ty)) => {
+                        match opaque_ty.opaque_ty_id.impl_trait_bounds(f.db) {
+                            Some(bounds) => Cow::Owned(bounds.subst(&opaque_ty.parameters).value),
+                            None => Cow::Borrowed(&[][..]),
+                        }
                     }
                     _ => Cow::Borrowed(&[][..]),
                 };
@@ -438,7 +433,10 @@ impl HirDisplay for Ty {
                                     let mut default_from = 0;
                                     for (i, parameter) in parameters.iter().enumerate() {
                                         match (parameter, default_parameters.get(i)) {
-                                            (&Ty::Unknown, _) | (_, None) => {
+                                            (_, None) => {
+                                                default_from = i + 1;
+                                            }
+                                            (parameter, _) if parameter.is_unknown() => {
                                                 default_from = i + 1;
                                             }
                                             (_, Some(default_parameter)) => {
@@ -493,12 +491,10 @@ impl HirDisplay for Ty {
             }
             Ty::OpaqueType(opaque_ty_id, parameters) => {
                 match opaque_ty_id {
-                    &OpaqueTyId::ReturnTypeImplTrait(func, idx) => {
-                        let datas =
-                            f.db.return_type_impl_traits(func).expect("impl trait id without data");
-                        let data = (*datas)
-                            .as_ref()
-                            .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                    OpaqueTyId::ReturnTypeImplTrait(..) | OpaqueTyId::TypeAliasImplTrait(..) => {
+                        let data = opaque_ty_id
+                            .impl_trait_bounds(f.db)
+                            .expect("impl trait id without data");
                         let bounds = data.subst(&parameters);
                         write_bounds_like_dyn_trait_with_prefix("impl", &bounds.value, f)?;
                         // FIXME: it would maybe be good to distinguish this from the alias type (when debug printing), and to show the substitution
@@ -559,12 +555,11 @@ impl HirDisplay for Ty {
             Ty::Alias(AliasTy::Projection(p_ty)) => p_ty.hir_fmt(f)?,
             Ty::Alias(AliasTy::Opaque(opaque_ty)) => {
                 match opaque_ty.opaque_ty_id {
-                    OpaqueTyId::ReturnTypeImplTrait(func, idx) => {
-                        let datas =
-                            f.db.return_type_impl_traits(func).expect("impl trait id without data");
-                        let data = (*datas)
-                            .as_ref()
-                            .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                    OpaqueTyId::ReturnTypeImplTrait(..) | OpaqueTyId::TypeAliasImplTrait(..) => {
+                        let data = opaque_ty
+                            .opaque_ty_id
+                            .impl_trait_bounds(f.db)
+                            .expect("impl trait id without data");
                         let bounds = data.subst(&opaque_ty.parameters);
                         write_bounds_like_dyn_trait_with_prefix("impl", &bounds.value, f)?;
                     }
@@ -573,13 +568,16 @@ impl HirDisplay for Ty {
                     }
                 };
             }
-            Ty::Unknown => {
+            Ty::Unknown | Ty::Error => {
                 if f.display_target.is_source_code() {
                     return Err(HirDisplayError::DisplaySourceCodeError(
                         DisplaySourceCodeError::UnknownType,
                     ));
                 }
-                write!(f, "{{unknown}}")?;
+                match self {
+                    Ty::Error => write!(f, "{{error}}")?,
+                    _ => write!(f, "{{unknown}}")?,
+                }
             }
             Ty::InferenceVar(..) => write!(f, "_")?,
         }
@@ -620,6 +618,34 @@ fn fn_traits(db: &dyn DefDatabase, trait_: TraitId) -> impl Iterator<Item = Trai
     ArrayVec::from(fn_traits).into_iter().flatten().flat_map(|it| it.as_trait())
 }
 
+/// The pointer width of the target `krate` is built for, in bits, as set by
+/// its `target_pointer_width` cfg.
+pub fn target_pointer_width(db: &dyn HirDatabase, krate: CrateId) -> Option<u32> {
+    let crate_graph = db.crate_graph();
+    let width = crate_graph[krate].cfg_options.get_cfg_values("target_pointer_width").next()?;
+    width.parse().ok()
+}
+
+/// Renders `scalar` like `HirDisplay` does, but with the width of `usize` and
+/// `isize` on the target of `krate` spelled out, e.g. `usize (64 bits)`. If we
+/// don't know the target's pointer width, they are rendered without it.
+pub fn display_scalar_with_target_width(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    scalar: Scalar,
+) -> String {
+    let name = Ty::Scalar(scalar).display(db).to_string();
+    match scalar {
+        Scalar::Int(IntTy::Isize) | Scalar::Uint(UintTy::Usize) => {
+            match target_pointer_width(db, krate) {
+                Some(width) => format!("{} ({} bits)", name, width),
+                None => name,
+            }
+        }
+        _ => name,
+    }
+}
+
 pub fn write_bounds_like_dyn_trait_with_prefix(
     prefix: &str,
     predicates: &[GenericPredicate],
@@ -698,7 +724,7 @@ fn write_bounds_like_dyn_trait(
                 write!(f, "{} = ", type_alias.name)?;
                 projection_pred.ty.hir_fmt(f)?;
             }
-            GenericPredicate::Error => {
+            GenericPredicate::Opaque(_) | GenericPredicate::Error => {
                 if angle_open {
                     // impl Trait<X, {error}>
                     write!(f, ", ")?;
@@ -769,6 +795,11 @@ impl HirDisplay for GenericPredicate {
                 )?;
                 projection_pred.ty.hir_fmt(f)?;
             }
+            GenericPredicate::Opaque(opaque_pred) => {
+                Ty::Alias(AliasTy::Opaque(opaque_pred.opaque_ty.clone())).hir_fmt(f)?;
+                write!(f, " = ")?;
+                opaque_pred.ty.hir_fmt(f)?;
+            }
             GenericPredicate::Error => write!(f, "{{error}}")?,
         }
         Ok(())
@@ -784,6 +815,7 @@ impl HirDisplay for Lifetime {
                 write!(f, "{}", &param_data.name)
             }
             Lifetime::Static => write!(f, "'static"),
+            Lifetime::Erased => write!(f, "'_"),
         }
     }
 }
@@ -803,6 +835,11 @@ impl HirDisplay for Obligation {
                 proj.ty.hir_fmt(f)?;
                 write!(f, ")")
             }
+            Obligation::WellFormed(ty) => {
+                write!(f, "WellFormed(")?;
+                ty.hir_fmt(f)?;
+                write!(f, ")")
+            }
         }
     }
 }
@@ -0,0 +1,344 @@
+//! Fuzzing the conversion of Chalk types back to ours, see `crates/hir_ty/fuzz`.
+//! This is only built with the `fuzz` feature.
+//!
+//! The fuzzer's bytes are decoded into a `chalk_ir::Ty` by `TyGen`. It only
+//! produces types of the form we pass to Chalk ourselves (e.g. fn pointers
+//! never bind variables), and the ids in them are those of the items in
+//! `FIXTURE`, since ids made up from random bytes aren't interned in the
+//! database.
+//!
+//! Any panic on these types is a bug. The seeds in `fuzz/seeds/from_chalk`
+//! cover some tricky types, like nested fn pointers.
+
+use base_db::{fixture::WithFixture, salsa::InternKey};
+use chalk_ir::{
+    cast::Cast, DebruijnIndex, FloatTy, IntTy, Mutability, PlaceholderIndex, Scalar, UintTy,
+    UniverseIndex,
+};
+use hir_def::{db::DefDatabase, TypeParamId};
+use hir_expand::name::name;
+
+use crate::{
+    db::HirDatabase,
+    test_db::TestDB,
+    traits::chalk::{
+        from_chalk, AdtId, AssocTypeId, FnDefId, ForeignDefId, Interner, OpaqueTyId, ToChalk,
+        TraitId, TypeAliasAsAssocType, TypeAliasAsForeignType,
+    },
+    CallableDefId, Ty,
+};
+
+/// The items whose ids `TyGen` puts in types.
+const FIXTURE: &str = r#"
+struct S<T>(T);
+trait Tr { type Output; }
+fn generic<T>() -> impl Tr {
+    let f = || ();
+}
+"#;
+
+/// A database with the items of `FIXTURE`. Setting it up takes much longer
+/// than converting a type, so fuzz targets should reuse it.
+pub struct FuzzDb {
+    db: TestDB,
+    ids: Ids,
+}
+
+struct Ids {
+    adt: AdtId,
+    assoc_ty: AssocTypeId,
+    foreign: ForeignDefId,
+    fn_def: FnDefId,
+    opaque: OpaqueTyId,
+    closure: chalk_ir::ClosureId<Interner>,
+    placeholder: PlaceholderIndex,
+    trait_: TraitId,
+    /// We don't intern generators, so `from_chalk` doesn't look this up.
+    generator: chalk_ir::GeneratorId<Interner>,
+}
+
+impl Default for FuzzDb {
+    fn default() -> FuzzDb {
+        let (db, file_id) = TestDB::with_single_file(FIXTURE);
+        let adt = db.first_adt(file_id);
+        let trait_ = db.first_trait(file_id);
+        let func = db.first_fn(file_id);
+        let assoc_ty = db.trait_data(trait_).associated_type_by_name(&name![Output]).unwrap();
+        let (local_id, _) = db.generic_params(func.into()).types.iter().next().unwrap();
+        let type_param = db.intern_type_param_id(TypeParamId { parent: func.into(), local_id });
+        let closure = db
+            .infer(func.into())
+            .type_of_expr
+            .iter()
+            .find_map(|(_, ty)| match ty {
+                Ty::Closure(def, expr, _) => Some(db.intern_closure((*def, *expr)).into()),
+                _ => None,
+            })
+            .unwrap();
+        let ids = Ids {
+            adt: chalk_ir::AdtId(adt),
+            assoc_ty: TypeAliasAsAssocType(assoc_ty).to_chalk(&db),
+            foreign: TypeAliasAsForeignType(assoc_ty).to_chalk(&db),
+            fn_def: CallableDefId::FunctionId(func).to_chalk(&db),
+            opaque: crate::OpaqueTyId::ReturnTypeImplTrait(func, 0).to_chalk(&db),
+            closure,
+            placeholder: PlaceholderIndex {
+                ui: UniverseIndex::ROOT,
+                idx: type_param.as_intern_id().as_usize(),
+            },
+            trait_: trait_.to_chalk(&db),
+            generator: chalk_ir::GeneratorId(base_db::salsa::InternId::from(0u32)),
+        };
+        FuzzDb { db, ids }
+    }
+}
+
+impl FuzzDb {
+    /// Decodes a Chalk type from `data` and converts it with `Ty::from_chalk`.
+    pub fn check_from_chalk(&self, data: &[u8]) {
+        let chalk_ty = TyGen { data: data.iter(), ids: &self.ids, binders: 0, depth: 0 }.ty();
+        from_chalk::<Ty, _>(&self.db, chalk_ty);
+    }
+}
+
+/// Nesting deeper than this produces the error type, to keep types small.
+const MAX_DEPTH: usize = 16;
+
+/// The number of `TyKind`s `TyGen::ty_of_kind` picks from; bytes past these
+/// pick the other kinds of generic arguments.
+const TY_KINDS: usize = 24;
+const LIFETIME_ARG: usize = TY_KINDS;
+const CONST_ARG: usize = TY_KINDS + 1;
+
+const SCALARS: [Scalar; 16] = [
+    Scalar::Bool,
+    Scalar::Char,
+    Scalar::Int(IntTy::Isize),
+    Scalar::Int(IntTy::I8),
+    Scalar::Int(IntTy::I16),
+    Scalar::Int(IntTy::I32),
+    Scalar::Int(IntTy::I64),
+    Scalar::Int(IntTy::I128),
+    Scalar::Uint(UintTy::Usize),
+    Scalar::Uint(UintTy::U8),
+    Scalar::Uint(UintTy::U16),
+    Scalar::Uint(UintTy::U32),
+    Scalar::Uint(UintTy::U64),
+    Scalar::Uint(UintTy::U128),
+    Scalar::Float(FloatTy::F32),
+    Scalar::Float(FloatTy::F64),
+];
+
+struct TyGen<'a> {
+    data: std::slice::Iter<'a, u8>,
+    ids: &'a Ids,
+    /// The number of binders we're inside of.
+    binders: usize,
+    depth: usize,
+}
+
+impl TyGen<'_> {
+    /// The next byte, modulo `n`. Once the data runs out, this is always 0.
+    fn next(&mut self, n: usize) -> usize {
+        self.data.next().map_or(0, |&b| b as usize % n)
+    }
+
+    fn is_done(&self) -> bool {
+        self.depth >= MAX_DEPTH || self.data.as_slice().is_empty()
+    }
+
+    fn ty(&mut self) -> chalk_ir::Ty<Interner> {
+        if self.is_done() {
+            return chalk_ir::TyKind::Error.intern(&Interner);
+        }
+        let kind = self.next(TY_KINDS);
+        self.ty_of_kind(kind)
+    }
+
+    /// A generic argument. A byte picks the same type kind here as in `ty`, so
+    /// the seeds keep their meaning.
+    fn arg(&mut self) -> chalk_ir::GenericArg<Interner> {
+        if self.is_done() {
+            return chalk_ir::TyKind::Error.intern(&Interner).cast(&Interner);
+        }
+        match self.next(TY_KINDS + 2) {
+            LIFETIME_ARG => self.lifetime().cast(&Interner),
+            CONST_ARG => usize_const().cast(&Interner),
+            kind => self.ty_of_kind(kind).cast(&Interner),
+        }
+    }
+
+    fn ty_of_kind(&mut self, kind: usize) -> chalk_ir::Ty<Interner> {
+        self.depth += 1;
+        let kind = match kind {
+            0 => chalk_ir::TyKind::Error,
+            1 => chalk_ir::TyKind::Never,
+            2 => chalk_ir::TyKind::Str,
+            3 => chalk_ir::TyKind::Scalar(SCALARS[self.next(SCALARS.len())]),
+            4 => {
+                let len = self.next(4);
+                chalk_ir::TyKind::Tuple(len, self.substitution(len))
+            }
+            5 => chalk_ir::TyKind::Array(self.ty(), usize_const()),
+            6 => chalk_ir::TyKind::Slice(self.ty()),
+            7 => chalk_ir::TyKind::Raw(self.mutability(), self.ty()),
+            8 => {
+                let lifetime = chalk_ir::LifetimeData::Static.intern(&Interner);
+                chalk_ir::TyKind::Ref(self.mutability(), lifetime, self.ty())
+            }
+            9 => {
+                let variadic = self.next(2) == 1;
+                let safety = if self.next(2) == 1 {
+                    chalk_ir::Safety::Unsafe
+                } else {
+                    chalk_ir::Safety::Safe
+                };
+                let num_params = self.next(4);
+                self.binders += 1;
+                let substitution = chalk_ir::FnSubst(self.substitution(num_params + 1));
+                self.binders -= 1;
+                chalk_ir::TyKind::Function(chalk_ir::FnPointer {
+                    num_binders: 0,
+                    sig: chalk_ir::FnSig { abi: (), safety, variadic },
+                    substitution,
+                })
+            }
+            10 => chalk_ir::TyKind::BoundVar(self.free_bound_var()),
+            11 => chalk_ir::TyKind::InferenceVar(
+                chalk_ir::InferenceVar::from(self.next(3) as u32),
+                chalk_ir::TyVariableKind::General,
+            ),
+            12 => chalk_ir::TyKind::Adt(self.ids.adt, self.some_substitution()),
+            13 => chalk_ir::TyKind::AssociatedType(self.ids.assoc_ty, self.some_substitution()),
+            14 => chalk_ir::TyKind::OpaqueType(self.ids.opaque, self.some_substitution()),
+            15 => chalk_ir::TyKind::FnDef(self.ids.fn_def, self.some_substitution()),
+            16 => chalk_ir::TyKind::Closure(self.ids.closure, self.some_substitution()),
+            17 => chalk_ir::TyKind::Foreign(self.ids.foreign),
+            18 => chalk_ir::TyKind::Placeholder(self.ids.placeholder),
+            19 => chalk_ir::TyKind::Alias(chalk_ir::AliasTy::Projection(chalk_ir::ProjectionTy {
+                associated_ty_id: self.ids.assoc_ty,
+                substitution: self.some_substitution(),
+            })),
+            20 => chalk_ir::TyKind::Alias(chalk_ir::AliasTy::Opaque(chalk_ir::OpaqueTy {
+                opaque_ty_id: self.ids.opaque,
+                substitution: self.some_substitution(),
+            })),
+            21 => self.dyn_ty(),
+            22 => chalk_ir::TyKind::Generator(self.ids.generator, self.some_substitution()),
+            _ => chalk_ir::TyKind::GeneratorWitness(self.ids.generator, self.some_substitution()),
+        };
+        self.depth -= 1;
+        kind.intern(&Interner)
+    }
+
+    /// `dyn Tr`, possibly with a binding for `Tr::Output`.
+    fn dyn_ty(&mut self) -> chalk_ir::TyKind<Interner> {
+        // the `dyn`'s self type, bound one binder out from the where clauses'
+        let self_ty = chalk_ir::TyKind::BoundVar(chalk_ir::BoundVar::new(DebruijnIndex::ONE, 0))
+            .intern(&Interner);
+        let self_subst = chalk_ir::Substitution::from1(&Interner, self_ty);
+        let trait_ref =
+            chalk_ir::TraitRef { trait_id: self.ids.trait_, substitution: self_subst.clone() };
+        let mut where_clauses = vec![chalk_ir::WhereClause::Implemented(trait_ref)];
+        if self.next(2) == 1 {
+            let alias = chalk_ir::AliasTy::Projection(chalk_ir::ProjectionTy {
+                associated_ty_id: self.ids.assoc_ty,
+                substitution: self_subst,
+            });
+            self.binders += 2;
+            let ty = self.ty();
+            self.binders -= 2;
+            where_clauses.push(chalk_ir::WhereClause::AliasEq(chalk_ir::AliasEq { alias, ty }));
+        }
+        let where_clauses = chalk_ir::QuantifiedWhereClauses::from_iter(
+            &Interner,
+            where_clauses.into_iter().map(|it| chalk_ir::Binders::empty(&Interner, it)),
+        );
+        let self_kind = chalk_ir::VariableKind::Ty(chalk_ir::TyVariableKind::General);
+        chalk_ir::TyKind::Dyn(chalk_ir::DynTy {
+            bounds: chalk_ir::Binders::new(
+                chalk_ir::VariableKinds::from1(&Interner, self_kind),
+                where_clauses,
+            ),
+            lifetime: chalk_ir::LifetimeData::Static.intern(&Interner),
+        })
+    }
+
+    fn substitution(&mut self, len: usize) -> chalk_ir::Substitution<Interner> {
+        let args = (0..len).map(|_| self.arg()).collect::<Vec<_>>();
+        chalk_ir::Substitution::from_iter(&Interner, args)
+    }
+
+    /// A substitution of any length, for items we don't check the number of
+    /// generic arguments of.
+    fn some_substitution(&mut self) -> chalk_ir::Substitution<Interner> {
+        let len = self.next(4);
+        self.substitution(len)
+    }
+
+    fn lifetime(&mut self) -> chalk_ir::Lifetime<Interner> {
+        let data = match self.next(3) {
+            0 => chalk_ir::LifetimeData::Static,
+            1 => chalk_ir::LifetimeData::Erased,
+            _ => chalk_ir::LifetimeData::BoundVar(self.free_bound_var()),
+        };
+        data.intern(&Interner)
+    }
+
+    fn free_bound_var(&mut self) -> chalk_ir::BoundVar {
+        // bound vars never refer to the binders we're in, since fn pointers
+        // don't bind anything, and a `dyn` only binds its self type
+        let debruijn = DebruijnIndex::new((self.binders + self.next(3)) as u32);
+        chalk_ir::BoundVar::new(debruijn, self.next(3))
+    }
+
+    fn mutability(&mut self) -> Mutability {
+        if self.next(2) == 1 {
+            Mutability::Mut
+        } else {
+            Mutability::Not
+        }
+    }
+}
+
+fn usize_const() -> chalk_ir::Const<Interner> {
+    let usize_ty = chalk_ir::TyKind::Scalar(Scalar::Uint(UintTy::Usize)).intern(&Interner);
+    chalk_ir::ConstData {
+        ty: usize_ty,
+        value: chalk_ir::ConstValue::Concrete(chalk_ir::ConcreteConst { interned: () }),
+    }
+    .intern(&Interner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn from_chalk_seeds_do_not_panic() {
+        let db = FuzzDb::default();
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/seeds/from_chalk");
+        let mut count = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let data = fs::read(&path).unwrap();
+            let _p = stdx::panic_context::enter(path.display().to_string());
+            db.check_from_chalk(&data);
+            count += 1;
+        }
+        assert!(count > 0, "no seeds in {}", dir);
+    }
+
+    #[test]
+    fn every_kind_of_generic_arg_converts() {
+        let db = FuzzDb::default();
+        for kind in 0..TY_KINDS + 2 {
+            // a one-element tuple, so `kind` is read as a generic argument
+            let data = [4, 1, kind as u8, 3, 1, 1, 2, 3, 4];
+            let _p = stdx::panic_context::enter(format!("{:?}", data));
+            db.check_from_chalk(&data);
+        }
+    }
+}
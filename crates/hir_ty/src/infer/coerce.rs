@@ -4,11 +4,11 @@
 //!
 //! See: https://doc.rust-lang.org/nomicon/coercions.html
 
-use chalk_ir::{Mutability, TyVariableKind};
+use chalk_ir::TyVariableKind;
 use hir_def::lang_item::LangItemTarget;
 use test_utils::mark;
 
-use crate::{autoderef, traits::Solution, Obligation, Substs, TraitRef, Ty};
+use crate::{autoderef, traits::Solution, Mutability, Obligation, Substs, TraitRef, Ty};
 
 use super::{InEnvironment, InferenceContext};
 
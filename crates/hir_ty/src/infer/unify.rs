@@ -2,15 +2,16 @@
 
 use std::borrow::Cow;
 
-use chalk_ir::{FloatTy, IntTy, TyVariableKind};
+use chalk_ir::TyVariableKind;
 use ena::unify::{InPlaceUnificationTable, NoError, UnifyKey, UnifyValue};
 
 use test_utils::mark;
 
 use super::{InferenceContext, Obligation};
 use crate::{
+    primitive::{FloatTy, IntTy},
     BoundVar, Canonical, DebruijnIndex, GenericPredicate, InEnvironment, InferenceVar, Scalar,
-    Substs, Ty, TypeWalk,
+    Substs, Ty, TypeWalk, VariableKind,
 };
 
 impl<'a> InferenceContext<'a> {
@@ -78,7 +79,7 @@ impl<'a, 'b> Canonicalizer<'a, 'b> {
     }
 
     fn into_canonicalized<T>(self, result: T) -> Canonicalized<T> {
-        let kinds = self.free_vars.iter().map(|&(_, k)| k).collect();
+        let kinds = self.free_vars.iter().map(|&(_, k)| VariableKind::Ty(k)).collect();
         Canonicalized { value: Canonical { value: result, kinds }, free_vars: self.free_vars }
     }
 
@@ -98,6 +99,9 @@ impl<'a, 'b> Canonicalizer<'a, 'b> {
             Obligation::Projection(pr) => {
                 Obligation::Projection(self.do_canonicalize(pr, DebruijnIndex::INNERMOST))
             }
+            Obligation::WellFormed(ty) => {
+                Obligation::WellFormed(self.do_canonicalize(ty, DebruijnIndex::INNERMOST))
+            }
         };
         self.into_canonicalized(InEnvironment {
             value: result,
@@ -108,6 +112,9 @@ impl<'a, 'b> Canonicalizer<'a, 'b> {
 
 impl<T> Canonicalized<T> {
     pub(super) fn decanonicalize_ty(&self, mut ty: Ty) -> Ty {
+        if self.value.is_trivial() {
+            return ty;
+        }
         ty.walk_mut_binders(
             &mut |ty, binders| {
                 if let &mut Ty::BoundVar(bound) = ty {
@@ -127,23 +134,18 @@ impl<T> Canonicalized<T> {
         ctx: &mut InferenceContext<'_>,
         solution: Canonical<Substs>,
     ) {
+        if self.value.is_trivial() {
+            // there are no variables for the solution to fill in
+            mark::hit!(trivial_canonical_solution_not_applied);
+            return;
+        }
         // the solution may contain new variables, which we need to convert to new inference vars
-        let new_vars = Substs(
-            solution
-                .kinds
-                .iter()
-                .map(|k| match k {
-                    TyVariableKind::General => ctx.table.new_type_var(),
-                    TyVariableKind::Integer => ctx.table.new_integer_var(),
-                    TyVariableKind::Float => ctx.table.new_float_var(),
-                })
-                .collect(),
-        );
-        for (i, ty) in solution.value.into_iter().enumerate() {
+        let solution = ctx.table.instantiate_canonical(solution);
+        for (i, ty) in solution.iter().enumerate() {
             let (v, k) = self.free_vars[i];
             // eagerly replace projections in the type; we may be getting types
             // e.g. from where clauses where this hasn't happened yet
-            let ty = ctx.normalize_associated_types_in(ty.clone().subst_bound_vars(&new_vars));
+            let ty = ctx.normalize_associated_types_in(ty.clone());
             ctx.table.unify(&Ty::InferenceVar(v, k), &ty);
         }
     }
@@ -248,6 +250,24 @@ impl InferenceTable {
         self.new_var(TyVariableKind::General, true)
     }
 
+    /// Replaces the bound variables of `canonical` with fresh inference
+    /// variables of the kinds it records; the inverse of canonicalization.
+    /// Const variables become `Ty::Unknown`, since we have no const inference
+    /// variables.
+    pub(crate) fn instantiate_canonical<T: TypeWalk>(&mut self, canonical: Canonical<T>) -> T {
+        let vars = Substs(
+            canonical
+                .kinds
+                .iter()
+                .map(|kind| match kind {
+                    VariableKind::Ty(kind) => self.new_var(*kind, false),
+                    VariableKind::Const(_) => Ty::Unknown,
+                })
+                .collect(),
+        );
+        canonical.value.subst_bound_vars(&vars)
+    }
+
     pub(crate) fn resolve_ty_completely(&mut self, ty: Ty) -> Ty {
         self.resolve_ty_completely_inner(&mut Vec::new(), ty)
     }
@@ -293,7 +313,7 @@ impl InferenceTable {
 
     pub(super) fn unify_inner_trivial(&mut self, ty1: &Ty, ty2: &Ty, depth: usize) -> bool {
         match (ty1, ty2) {
-            (Ty::Unknown, _) | (_, Ty::Unknown) => true,
+            (Ty::Unknown, _) | (_, Ty::Unknown) | (Ty::Error, _) | (_, Ty::Error) => true,
 
             (Ty::Placeholder(p1), Ty::Placeholder(p2)) if *p1 == *p2 => true,
 
@@ -521,3 +541,36 @@ impl UnifyValue for TypeVarValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instantiate_canonical_uses_fresh_vars_of_recorded_kinds() {
+        let mut table = InferenceTable::new();
+        table.new_type_var();
+        let bound = |index| Ty::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, index));
+        let canonical = Canonical::new(
+            Ty::Tuple(3, Substs(vec![bound(0), bound(1), bound(0)].into())),
+            vec![
+                VariableKind::Ty(TyVariableKind::General),
+                VariableKind::Ty(TyVariableKind::Integer),
+            ],
+        );
+
+        let ty = table.instantiate_canonical(canonical);
+        let vars = match &ty {
+            Ty::Tuple(_, substs) => substs.iter().cloned().collect::<Vec<_>>(),
+            _ => panic!("expected a tuple, got {:?}", ty),
+        };
+        assert_eq!(
+            vars,
+            vec![
+                Ty::InferenceVar(InferenceVar::from_inner(TypeVarId(1)), TyVariableKind::General),
+                Ty::InferenceVar(InferenceVar::from_inner(TypeVarId(2)), TyVariableKind::Integer),
+                Ty::InferenceVar(InferenceVar::from_inner(TypeVarId(1)), TyVariableKind::General),
+            ]
+        );
+    }
+}
@@ -13,30 +13,33 @@ mod op;
 mod lower;
 pub(crate) mod infer;
 pub(crate) mod utils;
+mod variance;
 
 pub mod display;
 pub mod db;
 pub mod diagnostics;
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
 
 #[cfg(test)]
 mod tests;
-#[cfg(test)]
+#[cfg(any(test, feature = "fuzz"))]
+#[cfg_attr(not(test), allow(dead_code))]
 mod test_db;
 
 use std::{iter, mem, ops::Deref, sync::Arc};
 
 use base_db::salsa;
 use hir_def::{
-    builtin_type::BuiltinType, expr::ExprId, type_ref::Rawness, AdtId, AssocContainerId,
-    DefWithBodyId, FunctionId, GenericDefId, HasModule, LifetimeParamId, Lookup, TraitId,
-    TypeAliasId, TypeParamId,
+    builtin_type::BuiltinType, expr::ExprId, type_ref::Rawness, AdtId, DefWithBodyId, FunctionId,
+    GenericDefId, HasModule, LifetimeParamId, TraitId, TypeAliasId, TypeParamId,
 };
 use itertools::Itertools;
 
 use crate::{
     db::HirDatabase,
     display::HirDisplay,
-    utils::{generics, make_mut_slice, Generics},
+    utils::{assoc_ty_trait, generics, make_mut_slice, Generics},
 };
 
 pub use autoderef::autoderef;
@@ -45,14 +48,20 @@ pub use lower::{
     associated_type_shorthand_candidates, callable_item_sig, CallableDefId, ImplTraitLoweringMode,
     TyDefId, TyLoweringContext, ValueTyDefId,
 };
-pub use traits::{InEnvironment, Obligation, ProjectionPredicate, TraitEnvironment};
+pub use traits::{
+    skipped_where_clauses, InEnvironment, Obligation, OpaqueTyPredicate, ProjectionPredicate,
+    SkipReason, SkippedPredicate, SolverConfig, TraitEnvironment,
+};
 
-pub use chalk_ir::{BoundVar, DebruijnIndex, Mutability, Scalar, TyVariableKind};
+pub use chalk_ir::{BoundVar, DebruijnIndex, TyVariableKind};
+pub use primitive::Scalar;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub enum Lifetime {
     Parameter(LifetimeParamId),
     Static,
+    /// A lifetime we don't represent, e.g. one Chalk inferred.
+    Erased,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -76,10 +85,7 @@ impl ProjectionTy {
     }
 
     fn trait_(&self, db: &dyn HirDatabase) -> TraitId {
-        match self.associated_ty.lookup(db.upcast()).container {
-            AssocContainerId::TraitId(it) => it,
-            _ => panic!("projection ty without parent trait"),
-        }
+        assoc_ty_trait(db.upcast(), self.associated_ty).expect("projection ty without parent trait")
     }
 }
 
@@ -97,9 +103,35 @@ impl TypeWalk for ProjectionTy {
     }
 }
 
+/// Whether a reference or raw pointer is `mut`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Mutability {
+    Not,
+    Mut,
+}
+
+/// Whether a function pointer is `unsafe`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Safety {
+    Safe,
+    Unsafe,
+}
+
+/// How an ADT's subtyping relates to that of one of its type parameters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Variance {
+    /// `A<T>` is a subtype of `A<U>` if `T` is a subtype of `U`.
+    Covariant,
+    /// `A<T>` and `A<U>` are only related if `T` and `U` are the same type.
+    Invariant,
+    /// `A<T>` is a subtype of `A<U>` if `U` is a subtype of `T`.
+    Contravariant,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct FnSig {
     pub variadic: bool,
+    pub safety: Safety,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -114,6 +146,9 @@ pub enum AliasTy {
     /// A "projection" type corresponds to an (unnormalized)
     /// projection like `<P0 as Trait<P1..Pn>>::Foo`. Note that the
     /// trait and all its parameters are fully known.
+    ///
+    /// Lowering `T::Foo` produces this; once Chalk finds it can't be
+    /// normalized further, it comes back as a `Ty::AssociatedType`.
     Projection(ProjectionTy),
     /// An opaque type (`impl Trait`).
     ///
@@ -240,6 +275,29 @@ pub enum Ty {
     /// infer a better type here anyway -- for the IDE use case, we want to try
     /// to infer as much as possible even in the presence of type errors.
     Unknown,
+
+    /// Chalk's error type, or a type we got from Chalk but couldn't convert
+    /// back, e.g. because it refers to an id we never interned. This is an
+    /// error upstream rather than something we couldn't infer, but otherwise
+    /// it's treated like `Unknown`.
+    Error,
+}
+
+/// A single argument for a generic parameter.
+///
+/// Only type arguments are represented for now; lifetimes and consts are
+/// erased when lowering.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum GenericArg {
+    Ty(Ty),
+}
+
+impl GenericArg {
+    pub fn ty(&self) -> Option<&Ty> {
+        match self {
+            GenericArg::Ty(ty) => Some(ty),
+        }
+    }
 }
 
 /// A list of substitutions for generic parameters.
@@ -281,6 +339,26 @@ impl Substs {
         Substs(self.0[self.0.len() - std::cmp::min(self.0.len(), n)..].into())
     }
 
+    /// Returns the argument at `idx`, or `Ty::Unknown` if the substitution is
+    /// too short, which can happen for malformed programs.
+    pub fn get_or_unknown(&self, idx: usize) -> Ty {
+        self.0.get(idx).cloned().unwrap_or(Ty::Unknown)
+    }
+
+    /// Splits the substitution of a trait into the `Self` type, which always
+    /// comes first, and the trait's own parameters. Returns `None` if it's
+    /// empty.
+    pub fn split_self(&self) -> Option<(&Ty, &[Ty])> {
+        self.0.split_first()
+    }
+
+    /// Applies `f` to each type argument, without recursing into them. Other
+    /// kinds of arguments would be kept as they are, but since lifetimes and
+    /// consts are erased for now (see `GenericArg`), every argument is a type.
+    pub fn map_types(&self, f: impl FnMut(&Ty) -> Ty) -> Substs {
+        Substs(self.0.iter().map(f).collect())
+    }
+
     pub fn as_single(&self) -> &Ty {
         if self.0.len() != 1 {
             panic!("expected substs of len 1, got {:?}", self);
@@ -294,6 +372,8 @@ impl Substs {
     }
 
     /// Return Substs that replace each parameter by itself (i.e. `Ty::Param`).
+    /// This is the identity substitution for `def`, so prefer it over
+    /// building placeholders by hand.
     pub fn type_params(db: &dyn HirDatabase, def: impl Into<GenericDefId>) -> Substs {
         let params = generics(db.upcast(), def.into());
         Substs::type_params_for_generics(&params)
@@ -453,6 +533,20 @@ impl TraitRef {
     pub fn self_ty(&self) -> &Ty {
         &self.substs[0]
     }
+
+    /// The type parameters of the trait, not including the `Self` type.
+    pub fn type_params(&self) -> &[Ty] {
+        &self.substs[1..]
+    }
+
+    /// Whether this doesn't refer to an actual type, i.e. it has no `Self`
+    /// type or an unknown one (see `Ty::is_unknown`).
+    pub fn is_error(&self) -> bool {
+        match self.substs.0.first() {
+            Some(self_ty) => self_ty.is_unknown(),
+            None => true,
+        }
+    }
 }
 
 impl TypeWalk for TraitRef {
@@ -477,6 +571,8 @@ pub enum GenericPredicate {
     Implemented(TraitRef),
     /// An associated type bindings like in `Iterator<Item = T>`.
     Projection(ProjectionPredicate),
+    /// The hidden type of an `impl Trait`, which only comes from Chalk.
+    Opaque(OpaqueTyPredicate),
     /// We couldn't resolve the trait reference. (If some type parameters can't
     /// be resolved, they will just be Unknown).
     Error,
@@ -495,7 +591,7 @@ impl GenericPredicate {
         match self {
             GenericPredicate::Implemented(tr) => Some(tr.clone()),
             GenericPredicate::Projection(proj) => Some(proj.projection_ty.trait_ref(db)),
-            GenericPredicate::Error => None,
+            GenericPredicate::Opaque(_) | GenericPredicate::Error => None,
         }
     }
 }
@@ -505,6 +601,7 @@ impl TypeWalk for GenericPredicate {
         match self {
             GenericPredicate::Implemented(trait_ref) => trait_ref.walk(f),
             GenericPredicate::Projection(projection_pred) => projection_pred.walk(f),
+            GenericPredicate::Opaque(opaque_pred) => opaque_pred.walk(f),
             GenericPredicate::Error => {}
         }
     }
@@ -519,6 +616,7 @@ impl TypeWalk for GenericPredicate {
             GenericPredicate::Projection(projection_pred) => {
                 projection_pred.walk_mut_binders(f, binders)
             }
+            GenericPredicate::Opaque(opaque_pred) => opaque_pred.walk_mut_binders(f, binders),
             GenericPredicate::Error => {}
         }
     }
@@ -527,19 +625,34 @@ impl TypeWalk for GenericPredicate {
 /// Basically a claim (currently not validated / checked) that the contained
 /// type / trait ref contains no inference variables; any inference variables it
 /// contained have been replaced by bound variables, and `kinds` tells us how
-/// many there are and whether they were normal or float/int variables. This is
-/// used to erase irrelevant differences between types before using them in
-/// queries.
+/// many there are and whether they were normal, float/int or const variables.
+/// This is used to erase irrelevant differences between types before using them
+/// in queries.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Canonical<T> {
     pub value: T,
-    pub kinds: Arc<[TyVariableKind]>,
+    pub kinds: Arc<[VariableKind]>,
+}
+
+/// The kind of a variable bound by a `Canonical`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VariableKind {
+    Ty(TyVariableKind),
+    /// A const variable of the given type. Consts aren't represented in
+    /// `Substs` yet, so the variable's slot holds `Ty::Unknown`.
+    Const(Ty),
 }
 
 impl<T> Canonical<T> {
-    pub fn new(value: T, kinds: impl IntoIterator<Item = TyVariableKind>) -> Self {
+    pub fn new(value: T, kinds: impl IntoIterator<Item = VariableKind>) -> Self {
         Self { value, kinds: kinds.into_iter().collect() }
     }
+
+    /// Whether there are no variables, i.e. canonicalizing didn't change the
+    /// value.
+    pub fn is_trivial(&self) -> bool {
+        self.kinds.is_empty()
+    }
 }
 
 /// A function signature as seen by type inference: Several parameter types and
@@ -605,7 +718,7 @@ impl Ty {
     pub fn fn_ptr(sig: CallableSig) -> Self {
         Ty::Function(FnPointer {
             num_args: sig.params().len(),
-            sig: FnSig { variadic: sig.is_varargs },
+            sig: FnSig { variadic: sig.is_varargs, safety: Safety::Safe },
             substs: Substs(sig.params_and_return),
         })
     }
@@ -640,14 +753,18 @@ impl Ty {
         }
     }
 
-    pub fn strip_references(&self) -> &Ty {
+    /// Peels off all references, returning the innermost type and the number
+    /// of references around it.
+    pub fn strip_references(&self) -> (&Ty, usize) {
         let mut t: &Ty = self;
+        let mut count = 0;
 
         while let Ty::Ref(_mutability, parameters) = t {
             t = parameters.as_single();
+            count += 1;
         }
 
-        t
+        (t, count)
     }
 
     pub fn as_adt(&self) -> Option<(AdtId, &Substs)> {
@@ -664,6 +781,27 @@ impl Ty {
         }
     }
 
+    pub fn as_dyn_predicates(&self) -> Option<&[GenericPredicate]> {
+        match self {
+            Ty::Dyn(predicates) => Some(predicates),
+            _ => None,
+        }
+    }
+
+    pub fn as_fn_ptr(&self) -> Option<&FnPointer> {
+        match self {
+            Ty::Function(fn_ptr) => Some(fn_ptr),
+            _ => None,
+        }
+    }
+
+    pub fn as_closure(&self) -> Option<(DefWithBodyId, ExprId, &Substs)> {
+        match self {
+            Ty::Closure(def, expr, substs) => Some((*def, *expr, substs)),
+            _ => None,
+        }
+    }
+
     pub fn as_generic_def(&self) -> Option<GenericDefId> {
         match *self {
             Ty::Adt(adt, ..) => Some(adt.into()),
@@ -678,8 +816,9 @@ impl Ty {
         matches!(self, Ty::Never)
     }
 
+    /// Whether this is `Ty::Unknown` or `Ty::Error`.
     pub fn is_unknown(&self) -> bool {
-        matches!(self, Ty::Unknown)
+        matches!(self, Ty::Unknown | Ty::Error)
     }
 
     pub fn equals_ctor(&self, other: &Ty) -> bool {
@@ -708,13 +847,10 @@ impl Ty {
 
     /// If this is a `dyn Trait` type, this returns the `Trait` part.
     pub fn dyn_trait_ref(&self) -> Option<&TraitRef> {
-        match self {
-            Ty::Dyn(bounds) => bounds.get(0).and_then(|b| match b {
-                GenericPredicate::Implemented(trait_ref) => Some(trait_ref),
-                _ => None,
-            }),
+        self.as_dyn_predicates()?.first().and_then(|b| match b {
+            GenericPredicate::Implemented(trait_ref) => Some(trait_ref),
             _ => None,
-        }
+        })
     }
 
     /// If this is a `dyn Trait`, returns that trait.
@@ -835,24 +971,21 @@ impl Ty {
                             None
                         }
                     }
-                    OpaqueTyId::ReturnTypeImplTrait(..) => None,
+                    OpaqueTyId::ReturnTypeImplTrait(..) | OpaqueTyId::TypeAliasImplTrait(..) => {
+                        None
+                    }
                 }
             }
             Ty::Alias(AliasTy::Opaque(opaque_ty)) => {
                 let predicates = match opaque_ty.opaque_ty_id {
-                    OpaqueTyId::ReturnTypeImplTrait(func, idx) => {
-                        db.return_type_impl_traits(func).map(|it| {
-                            let data = (*it)
-                                .as_ref()
-                                .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
-                            data.subst(&opaque_ty.parameters)
-                        })
+                    OpaqueTyId::ReturnTypeImplTrait(..) | OpaqueTyId::TypeAliasImplTrait(..) => {
+                        opaque_ty.opaque_ty_id.impl_trait_bounds(db)
                     }
                     // It always has an parameter for Future::Output type.
                     OpaqueTyId::AsyncBlockTypeImplTrait(..) => unreachable!(),
                 };
 
-                predicates.map(|it| it.value)
+                predicates.map(|it| it.subst(&opaque_ty.parameters).value)
             }
             Ty::Placeholder(id) => {
                 let generic_params = db.generic_params(id.parent);
@@ -876,17 +1009,9 @@ impl Ty {
 
     pub fn associated_type_parent_trait(&self, db: &dyn HirDatabase) -> Option<TraitId> {
         match self {
-            Ty::AssociatedType(type_alias_id, ..) => {
-                match type_alias_id.lookup(db.upcast()).container {
-                    AssocContainerId::TraitId(trait_id) => Some(trait_id),
-                    _ => None,
-                }
-            }
+            Ty::AssociatedType(type_alias_id, ..) => assoc_ty_trait(db.upcast(), *type_alias_id),
             Ty::Alias(AliasTy::Projection(projection_ty)) => {
-                match projection_ty.associated_ty.lookup(db.upcast()).container {
-                    AssocContainerId::TraitId(trait_id) => Some(trait_id),
-                    _ => None,
-                }
+                assoc_ty_trait(db.upcast(), projection_ty.associated_ty)
             }
             _ => None,
         }
@@ -900,6 +1025,15 @@ pub trait TypeWalk {
     fn walk_mut(&mut self, f: &mut impl FnMut(&mut Ty)) {
         self.walk_mut_binders(&mut |ty, _binders| f(ty), DebruijnIndex::INNERMOST);
     }
+
+    /// Whether `Ty::Unknown` or `Ty::Error` occurs anywhere in this, e.g. because of an
+    /// earlier error.
+    fn contains_unknown(&self) -> bool {
+        let mut res = false;
+        self.walk(&mut |ty| res |= ty.is_unknown());
+        res
+    }
+
     /// Walk the type, counting entered binders.
     ///
     /// `Ty::Bound` variables use DeBruijn indexing, which means that 0 refers
@@ -1064,9 +1198,31 @@ impl<T: TypeWalk> TypeWalk for Vec<T> {
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub enum OpaqueTyId {
     ReturnTypeImplTrait(hir_def::FunctionId, u16),
+    /// The `idx`th `impl Trait` in a type alias, e.g. `type Foo = impl Trait;`.
+    TypeAliasImplTrait(TypeAliasId, u16),
     AsyncBlockTypeImplTrait(hir_def::DefWithBodyId, ExprId),
 }
 
+impl OpaqueTyId {
+    /// The bounds of the `impl Trait` this refers to, bound by the generic
+    /// parameters of the function or type alias it's written in, and by its own
+    /// self type. Async blocks have no written bounds, so this returns `None`
+    /// for them.
+    pub(crate) fn impl_trait_bounds(
+        self,
+        db: &dyn HirDatabase,
+    ) -> Option<Binders<Binders<Vec<GenericPredicate>>>> {
+        let (datas, idx) = match self {
+            OpaqueTyId::ReturnTypeImplTrait(func, idx) => (db.return_type_impl_traits(func)?, idx),
+            OpaqueTyId::TypeAliasImplTrait(alias, idx) => (db.type_alias_impl_traits(alias)?, idx),
+            OpaqueTyId::AsyncBlockTypeImplTrait(..) => return None,
+        };
+        Some((*datas).as_ref().map(|it| it.impl_traits[idx as usize].bounds.clone()))
+    }
+}
+
+/// The `impl Trait` types written in a function's return type or in a type
+/// alias.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct ReturnTypeImplTraits {
     pub(crate) impl_traits: Vec<ReturnTypeImplTrait>,
@@ -8,7 +8,6 @@
 use std::{iter, sync::Arc};
 
 use base_db::CrateId;
-use chalk_ir::Mutability;
 use hir_def::{
     adt::StructKind,
     builtin_type::BuiltinType,
@@ -33,8 +32,9 @@ use crate::{
         make_mut_slice, variant_data,
     },
     AliasTy, Binders, BoundVar, CallableSig, DebruijnIndex, FnPointer, FnSig, GenericPredicate,
-    OpaqueTy, OpaqueTyId, PolyFnSig, ProjectionPredicate, ProjectionTy, ReturnTypeImplTrait,
-    ReturnTypeImplTraits, Substs, TraitEnvironment, TraitRef, Ty, TypeWalk,
+    Mutability, OpaqueTy, OpaqueTyId, PolyFnSig, ProjectionPredicate, ProjectionTy,
+    ReturnTypeImplTrait, ReturnTypeImplTraits, Safety, Substs, TraitEnvironment, TraitRef, Ty,
+    TypeWalk,
 };
 
 #[derive(Debug)]
@@ -158,7 +158,7 @@ impl Ty {
             }
             TypeRef::RawPtr(inner, mutability) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
-                Ty::Raw(lower_to_chalk_mutability(*mutability), Substs::single(inner_ty))
+                Ty::Raw(lower_mutability(*mutability), Substs::single(inner_ty))
             }
             TypeRef::Array(inner) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
@@ -170,14 +170,14 @@ impl Ty {
             }
             TypeRef::Reference(inner, _, mutability) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
-                Ty::Ref(lower_to_chalk_mutability(*mutability), Substs::single(inner_ty))
+                Ty::Ref(lower_mutability(*mutability), Substs::single(inner_ty))
             }
             TypeRef::Placeholder => Ty::Unknown,
             TypeRef::Fn(params, is_varargs) => {
                 let substs = Substs(params.iter().map(|tr| Ty::from_hir(ctx, tr)).collect());
                 Ty::Function(FnPointer {
                     num_args: substs.len() - 1,
-                    sig: FnSig { variadic: *is_varargs },
+                    sig: FnSig { variadic: *is_varargs, safety: Safety::Safe },
                     substs,
                 })
             }
@@ -219,12 +219,18 @@ impl Ty {
                             });
                         ctx.opaque_type_data.borrow_mut()[idx as usize] = actual_opaque_type_data;
 
-                        let func = match ctx.resolver.generic_def() {
-                            Some(GenericDefId::FunctionId(f)) => f,
-                            _ => panic!("opaque impl trait lowering in non-function"),
+                        let (def, impl_trait_id) = match ctx.resolver.generic_def() {
+                            Some(GenericDefId::FunctionId(f)) => {
+                                (f.into(), OpaqueTyId::ReturnTypeImplTrait(f, idx))
+                            }
+                            Some(GenericDefId::TypeAliasId(t)) => {
+                                (t.into(), OpaqueTyId::TypeAliasImplTrait(t, idx))
+                            }
+                            _ => panic!(
+                                "opaque impl trait lowering outside a function or type alias"
+                            ),
                         };
-                        let impl_trait_id = OpaqueTyId::ReturnTypeImplTrait(func, idx);
-                        let generics = generics(ctx.db.upcast(), func.into());
+                        let generics = generics(ctx.db.upcast(), def);
                         let parameters = Substs::bound_vars(&generics, ctx.in_binders);
                         Ty::Alias(AliasTy::Opaque(OpaqueTy {
                             opaque_ty_id: impl_trait_id,
@@ -1106,9 +1112,12 @@ fn type_for_adt(db: &dyn HirDatabase, adt: AdtId) -> Binders<Ty> {
 fn type_for_type_alias(db: &dyn HirDatabase, t: TypeAliasId) -> Binders<Ty> {
     let generics = generics(db.upcast(), t.into());
     let resolver = t.resolver(db.upcast());
-    let ctx =
-        TyLoweringContext::new(db, &resolver).with_type_param_mode(TypeParamLoweringMode::Variable);
+    let ctx = TyLoweringContext::new(db, &resolver)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
+        .with_type_param_mode(TypeParamLoweringMode::Variable);
     if db.type_alias_data(t).is_extern {
+        // `extern` types can't have generic parameters, ignore any that were
+        // written anyway
         Binders::new(0, Ty::ForeignType(t))
     } else {
         let substs = Substs::bound_vars(&generics, DebruijnIndex::INNERMOST);
@@ -1250,18 +1259,38 @@ pub(crate) fn return_type_impl_traits(
         .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
         .with_type_param_mode(TypeParamLoweringMode::Variable);
     let _ret = Ty::from_hir(&ctx_ret, &data.ret_type);
-    let generics = generics(db.upcast(), def.into());
-    let num_binders = generics.len();
-    let return_type_impl_traits =
-        ReturnTypeImplTraits { impl_traits: ctx_ret.opaque_type_data.into_inner() };
-    if return_type_impl_traits.impl_traits.is_empty() {
+    collect_impl_traits(db, ctx_ret, def.into())
+}
+
+pub(crate) fn type_alias_impl_traits(
+    db: &dyn HirDatabase,
+    def: TypeAliasId,
+) -> Option<Arc<Binders<ReturnTypeImplTraits>>> {
+    // FIXME unify with type_for_type_alias instead of doing lowering twice, maybe
+    let data = db.type_alias_data(def);
+    let resolver = def.resolver(db.upcast());
+    let ctx = TyLoweringContext::new(db, &resolver)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
+        .with_type_param_mode(TypeParamLoweringMode::Variable);
+    let _ty = Ty::from_hir(&ctx, data.type_ref.as_ref().unwrap_or(&TypeRef::Error));
+    collect_impl_traits(db, ctx, def.into())
+}
+
+fn collect_impl_traits(
+    db: &dyn HirDatabase,
+    ctx: TyLoweringContext,
+    def: GenericDefId,
+) -> Option<Arc<Binders<ReturnTypeImplTraits>>> {
+    let num_binders = generics(db.upcast(), def).len();
+    let impl_traits = ReturnTypeImplTraits { impl_traits: ctx.opaque_type_data.into_inner() };
+    if impl_traits.impl_traits.is_empty() {
         None
     } else {
-        Some(Arc::new(Binders::new(num_binders, return_type_impl_traits)))
+        Some(Arc::new(Binders::new(num_binders, impl_traits)))
     }
 }
 
-pub(crate) fn lower_to_chalk_mutability(m: hir_def::type_ref::Mutability) -> Mutability {
+pub(crate) fn lower_mutability(m: hir_def::type_ref::Mutability) -> Mutability {
     match m {
         hir_def::type_ref::Mutability::Shared => Mutability::Not,
         hir_def::type_ref::Mutability::Mut => Mutability::Mut,
@@ -8,12 +8,16 @@ use std::{
 use base_db::{
     salsa, AnchoredPath, CrateId, FileId, FileLoader, FileLoaderDelegate, SourceDatabase, Upcast,
 };
-use hir_def::{db::DefDatabase, ModuleId};
+use hir_def::{
+    db::DefDatabase, AdtId, FunctionId, ModuleDefId, ModuleId, StructId, TraitId, TypeAliasId,
+};
 use hir_expand::db::AstDatabase;
 use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::TextRange;
 use test_utils::extract_annotations;
 
+use crate::{db::HirDatabase, SolverConfig};
+
 #[salsa::database(
     base_db::SourceDatabaseExtStorage,
     base_db::SourceDatabaseStorage,
@@ -22,11 +26,18 @@ use test_utils::extract_annotations;
     hir_def::db::DefDatabaseStorage,
     crate::db::HirDatabaseStorage
 )]
-#[derive(Default)]
 pub(crate) struct TestDB {
     storage: salsa::Storage<TestDB>,
     events: Mutex<Option<Vec<salsa::Event>>>,
 }
+
+impl Default for TestDB {
+    fn default() -> Self {
+        let mut this = Self { storage: Default::default(), events: Default::default() };
+        this.set_solver_config(SolverConfig::default());
+        this
+    }
+}
 impl fmt::Debug for TestDB {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TestDB").finish()
@@ -90,6 +101,66 @@ impl TestDB {
         panic!("Can't find module for file")
     }
 
+    /// Returns the items declared in the root module of `file_id` for which
+    /// `f` returns `Some`, in declaration order.
+    fn decls<T>(&self, file_id: FileId, f: impl FnMut(ModuleDefId) -> Option<T>) -> Vec<T> {
+        let module = self.module_for_file(file_id);
+        let def_map = module.def_map(self);
+        let decls = def_map[module.local_id].scope.declarations().filter_map(f).collect();
+        decls
+    }
+
+    pub(crate) fn fns(&self, file_id: FileId) -> Vec<FunctionId> {
+        self.decls(file_id, |decl| match decl {
+            ModuleDefId::FunctionId(it) => Some(it),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn first_fn(&self, file_id: FileId) -> FunctionId {
+        first(self.fns(file_id))
+    }
+
+    pub(crate) fn adts(&self, file_id: FileId) -> Vec<AdtId> {
+        self.decls(file_id, |decl| match decl {
+            ModuleDefId::AdtId(it) => Some(it),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn first_adt(&self, file_id: FileId) -> AdtId {
+        first(self.adts(file_id))
+    }
+
+    pub(crate) fn first_struct(&self, file_id: FileId) -> StructId {
+        first(self.decls(file_id, |decl| match decl {
+            ModuleDefId::AdtId(AdtId::StructId(it)) => Some(it),
+            _ => None,
+        }))
+    }
+
+    pub(crate) fn traits(&self, file_id: FileId) -> Vec<TraitId> {
+        self.decls(file_id, |decl| match decl {
+            ModuleDefId::TraitId(it) => Some(it),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn first_trait(&self, file_id: FileId) -> TraitId {
+        first(self.traits(file_id))
+    }
+
+    pub(crate) fn type_aliases(&self, file_id: FileId) -> Vec<TypeAliasId> {
+        self.decls(file_id, |decl| match decl {
+            ModuleDefId::TypeAliasId(it) => Some(it),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn first_type_alias(&self, file_id: FileId) -> TypeAliasId {
+        first(self.type_aliases(file_id))
+    }
+
     pub(crate) fn extract_annotations(&self) -> FxHashMap<FileId, Vec<(TextRange, String)>> {
         let mut files = Vec::new();
         let crate_graph = self.crate_graph();
@@ -114,6 +185,10 @@ impl TestDB {
     }
 }
 
+fn first<T>(decls: Vec<T>) -> T {
+    decls.into_iter().next().expect("no matching item in fixture")
+}
+
 impl TestDB {
     pub(crate) fn log(&self, f: impl FnOnce()) -> Vec<salsa::Event> {
         *self.events.lock().unwrap() = Some(Vec::new());
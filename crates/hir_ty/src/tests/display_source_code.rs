@@ -1,4 +1,12 @@
+use base_db::fixture::WithFixture;
+
 use super::check_types_source_code;
+use crate::{
+    display::display_scalar_with_target_width,
+    primitive::{IntTy, UintTy},
+    test_db::TestDB,
+    Scalar,
+};
 
 #[test]
 fn qualify_path_to_submodule() {
@@ -54,3 +62,32 @@ fn main() {
 "#,
     );
 }
+
+#[test]
+fn usize_width_follows_target_pointer_width() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+//- /main.rs crate:main cfg:target_pointer_width=32
+"#,
+    );
+    let krate = db.module_for_file(file_id).krate();
+    let usize_ty = Scalar::Uint(UintTy::Usize);
+    assert_eq!(display_scalar_with_target_width(&db, krate, usize_ty), "usize (32 bits)");
+    let isize_ty = Scalar::Int(IntTy::Isize);
+    assert_eq!(display_scalar_with_target_width(&db, krate, isize_ty), "isize (32 bits)");
+    let u64_ty = Scalar::Uint(UintTy::U64);
+    assert_eq!(display_scalar_with_target_width(&db, krate, u64_ty), "u64");
+
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+//- /main.rs crate:main cfg:target_pointer_width=64
+"#,
+    );
+    let krate = db.module_for_file(file_id).krate();
+    assert_eq!(display_scalar_with_target_width(&db, krate, usize_ty), "usize (64 bits)");
+
+    // without a target, we don't guess its width
+    let (db, file_id) = TestDB::with_single_file("");
+    let krate = db.module_for_file(file_id).krate();
+    assert_eq!(display_scalar_with_target_width(&db, krate, usize_ty), "usize");
+}
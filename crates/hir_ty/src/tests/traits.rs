@@ -3205,3 +3205,21 @@ fn f() {
         "#,
     );
 }
+
+#[test]
+fn concrete_obligation_skips_applying_the_solution() {
+    mark::check!(trivial_canonical_solution_not_applied);
+    check_types(
+        r#"
+trait Trait {
+    fn foo(&self) -> u32;
+}
+struct S;
+impl Trait for S {}
+fn test() {
+    let x = S.foo();
+    x;
+} //^ u32
+"#,
+    );
+}
@@ -1,20 +1,25 @@
 //! Trait solving using Chalk.
-use std::env::var;
 use std::sync::Arc;
 
 use base_db::CrateId;
 use chalk_ir::cast::Cast;
 use chalk_solve::{logging_db::LoggingRustIrDatabase, Solver};
-use hir_def::{lang_item::LangItemTarget, TraitId};
+use hir_def::{lang_item::LangItemTarget, resolver::HasResolver, GenericDefId, TraitId};
 use stdx::panic_context;
 
-use crate::{db::HirDatabase, DebruijnIndex, Substs};
+use crate::{db::HirDatabase, BoundVar, DebruijnIndex, InferenceVar, Substs, VariableKind};
 
-use super::{Canonical, GenericPredicate, HirDisplay, ProjectionTy, TraitRef, Ty, TypeWalk};
+use super::{
+    Canonical, GenericPredicate, HirDisplay, OpaqueTy, ProjectionTy, TraitRef, Ty, TypeWalk,
+};
 
 use self::chalk::{from_chalk, Interner, ToChalk};
 
 pub(crate) mod chalk;
+#[cfg(test)]
+mod tests;
+
+pub use self::chalk::{closure_ty, lookup_closure_ty, trait_id_from_chalk};
 
 /// This controls how much 'time' we give the Chalk solver before giving up.
 const CHALK_SOLVER_FUEL: i32 = 100;
@@ -25,12 +30,25 @@ struct ChalkContext<'a> {
     krate: CrateId,
 }
 
-fn create_chalk_solver() -> chalk_recursive::RecursiveSolver<Interner> {
-    let overflow_depth =
-        var("CHALK_OVERFLOW_DEPTH").ok().and_then(|s| s.parse().ok()).unwrap_or(100);
+/// Limits for the Chalk solver, see `HirDatabase::solver_config`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SolverConfig {
+    /// How deep the solver may recurse into subgoals.
+    pub overflow_depth: usize,
+    /// Goals bigger than this are truncated, which makes their solution
+    /// ambiguous.
+    pub max_size: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        SolverConfig { overflow_depth: 100, max_size: 30 }
+    }
+}
+
+fn create_chalk_solver(config: SolverConfig) -> chalk_recursive::RecursiveSolver<Interner> {
     let caching_enabled = true;
-    let max_size = var("CHALK_SOLVER_MAX_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(30);
-    chalk_recursive::RecursiveSolver::new(overflow_depth, max_size, caching_enabled)
+    chalk_recursive::RecursiveSolver::new(config.overflow_depth, config.max_size, caching_enabled)
 }
 
 /// A set of clauses that we assume to be true. E.g. if we are inside this function:
@@ -56,6 +74,14 @@ impl TraitEnvironment {
             _ => None,
         })
     }
+
+    /// Returns a copy of this environment which additionally assumes `pred`,
+    /// e.g. to ask what would hold if some bound were added.
+    pub fn with_predicate(&self, pred: GenericPredicate) -> Arc<TraitEnvironment> {
+        let mut predicates = self.predicates.clone();
+        predicates.push(pred);
+        Arc::new(TraitEnvironment { predicates })
+    }
 }
 
 /// Something (usually a goal), along with an environment.
@@ -71,6 +97,53 @@ impl<T> InEnvironment<T> {
     }
 }
 
+impl InEnvironment<Obligation> {
+    /// Returns the predicates the environment assumes, leaving out errors like
+    /// the conversion to Chalk does, together with the goal to prove.
+    pub fn assumptions_and_goal(&self) -> (Vec<GenericPredicate>, &Obligation) {
+        let assumptions =
+            self.environment.predicates.iter().filter(|pred| !pred.is_error()).cloned().collect();
+        (assumptions, &self.value)
+    }
+}
+
+/// Wraps `goal` in the environment of `def`, i.e. with the predicates `def`
+/// declares (with its type parameters as placeholders) assumed to hold.
+pub fn in_env_for_def(
+    db: &dyn HirDatabase,
+    def: GenericDefId,
+    goal: Obligation,
+) -> InEnvironment<Obligation> {
+    InEnvironment::new(TraitEnvironment::lower(db, &def.resolver(db.upcast())), goal)
+}
+
+/// The where clauses of `def` that the trait solver never gets to see, because
+/// they errored (e.g. their trait doesn't resolve).
+pub fn skipped_where_clauses(db: &dyn HirDatabase, def: GenericDefId) -> Vec<SkippedPredicate> {
+    let mut skipped = Vec::new();
+    chalk::convert_where_clauses(db, def, &Substs::type_params(db, def), Some(&mut skipped));
+    skipped
+}
+
+/// A predicate that was left out when converting to Chalk. Recording these
+/// lets us explain why a bound isn't taken into account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedPredicate {
+    pub predicate: GenericPredicate,
+    pub reason: SkipReason,
+}
+
+/// Why a `SkippedPredicate` was left out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// An errored where clause of an item.
+    ErrorInWhereClause,
+    /// An errored assumption of a `TraitEnvironment`.
+    ErrorInEnvironment,
+    /// An errored bound of a `dyn Trait` type.
+    ErrorInDynBound,
+}
+
 /// Something that needs to be proven (by Chalk) during type checking, e.g. that
 /// a certain type implements a certain trait. Proving the Obligation might
 /// result in additional information about inference variables.
@@ -79,7 +152,12 @@ pub enum Obligation {
     /// Prove that a certain type implements a trait (the type is the `Self` type
     /// parameter to the `TraitRef`).
     Trait(TraitRef),
+    /// Prove that a projection like `<T as Iterator>::Item` normalizes to a
+    /// certain type.
     Projection(ProjectionPredicate),
+    /// Prove that a type is well-formed, i.e. that the bounds its definition
+    /// puts on its parameters hold for their arguments.
+    WellFormed(Ty),
 }
 
 impl Obligation {
@@ -89,7 +167,29 @@ impl Obligation {
             GenericPredicate::Projection(projection_pred) => {
                 Some(Obligation::Projection(projection_pred))
             }
-            GenericPredicate::Error => None,
+            GenericPredicate::Opaque(_) | GenericPredicate::Error => None,
+        }
+    }
+}
+
+impl TypeWalk for Obligation {
+    fn walk(&self, f: &mut impl FnMut(&Ty)) {
+        match self {
+            Obligation::Trait(trait_ref) => trait_ref.walk(f),
+            Obligation::Projection(projection_pred) => projection_pred.walk(f),
+            Obligation::WellFormed(ty) => ty.walk(f),
+        }
+    }
+
+    fn walk_mut_binders(
+        &mut self,
+        f: &mut impl FnMut(&mut Ty, DebruijnIndex),
+        binders: DebruijnIndex,
+    ) {
+        match self {
+            Obligation::Trait(trait_ref) => trait_ref.walk_mut_binders(f, binders),
+            Obligation::Projection(projection_pred) => projection_pred.walk_mut_binders(f, binders),
+            Obligation::WellFormed(ty) => ty.walk_mut_binders(f, binders),
         }
     }
 }
@@ -116,6 +216,30 @@ impl TypeWalk for ProjectionPredicate {
     }
 }
 
+/// Says that the hidden type of an opaque type (`impl Trait`) is `ty`. We
+/// never produce these ourselves, but Chalk may hand them back to us.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OpaqueTyPredicate {
+    pub opaque_ty: OpaqueTy,
+    pub ty: Ty,
+}
+
+impl TypeWalk for OpaqueTyPredicate {
+    fn walk(&self, f: &mut impl FnMut(&Ty)) {
+        self.opaque_ty.parameters.walk(f);
+        self.ty.walk(f);
+    }
+
+    fn walk_mut_binders(
+        &mut self,
+        f: &mut impl FnMut(&mut Ty, DebruijnIndex),
+        binders: DebruijnIndex,
+    ) {
+        self.opaque_ty.parameters.walk_mut_binders(f, binders);
+        self.ty.walk_mut_binders(f, binders);
+    }
+}
+
 /// Solve a trait goal using Chalk.
 pub(crate) fn trait_solve_query(
     db: &dyn HirDatabase,
@@ -125,6 +249,7 @@ pub(crate) fn trait_solve_query(
     let _p = profile::span("trait_solve_query").detail(|| match &goal.value.value {
         Obligation::Trait(it) => db.trait_data(it.trait_).name.to_string(),
         Obligation::Projection(_) => "projection".to_string(),
+        Obligation::WellFormed(_) => "well-formed".to_string(),
     });
     log::info!("trait_solve_query({})", goal.value.value.display(db));
 
@@ -135,23 +260,80 @@ pub(crate) fn trait_solve_query(
         }
     }
 
-    let canonical = goal.to_chalk(db).cast(&Interner);
+    chalk::assert_canonicalized(&goal.value.value);
+    let u_canonical = canonical_goal_to_chalk(db, goal);
+    let solution = solve(db, krate, &u_canonical);
+    solution.map(|solution| solution_from_chalk(db, solution))
+}
+
+/// Like `db.trait_solve`, but goals over types containing `Ty::Unknown` are
+/// ambiguous without asking Chalk. That's for callers which would report a
+/// "no" as an error: the unknown type stems from an earlier error, so that'd
+/// be spurious. Autoderef and the like need Chalk's answer though, since the
+/// unknown type often doesn't matter, e.g. for `Wrapper<S, {unknown}>: Deref`.
+pub fn trait_solve_unless_unknown(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    goal: Canonical<InEnvironment<Obligation>>,
+) -> Option<Solution> {
+    if goal.value.value.contains_unknown() {
+        return Some(Solution::Ambig(Guidance::Unknown));
+    }
+    db.trait_solve(krate, goal)
+}
+
+/// A goal in the form it is passed to the Chalk solver.
+pub type ChalkGoal = chalk_ir::UCanonical<chalk_ir::InEnvironment<chalk_ir::Goal<Interner>>>;
 
+/// Converts a goal to the form the solver takes. This is just the `ToChalk`
+/// impls of `Canonical`, `InEnvironment` and `Obligation` nested, plus the
+/// universe count.
+fn canonical_goal_to_chalk(
+    db: &dyn HirDatabase,
+    goal: Canonical<InEnvironment<Obligation>>,
+) -> ChalkGoal {
+    let canonical = goal.to_chalk(db).cast(&Interner);
     // We currently don't deal with universes (I think / hope they're not yet
     // relevant for our use cases?)
-    let u_canonical = chalk_ir::UCanonical { canonical, universes: 1 };
-    let solution = solve(db, krate, &u_canonical);
-    solution.map(|solution| solution_from_chalk(db, solution))
+    chalk_ir::UCanonical { canonical, universes: 1 }
+}
+
+/// Canonicalizes `goal` and converts it to Chalk, for use as a key when
+/// caching solver results. Inference variables are numbered in order of first
+/// occurrence, so goals which only differ in how their inference variables are
+/// numbered get the same key.
+pub fn goal_cache_key(db: &dyn HirDatabase, goal: &InEnvironment<Obligation>) -> ChalkGoal {
+    let mut free_vars: Vec<InferenceVar> = Vec::new();
+    let mut kinds = Vec::new();
+    let value = goal.value.clone().fold_binders(
+        &mut |ty, binders| match ty {
+            Ty::InferenceVar(var, kind) => {
+                let idx = free_vars.iter().position(|&it| it == var).unwrap_or_else(|| {
+                    free_vars.push(var);
+                    kinds.push(VariableKind::Ty(kind));
+                    free_vars.len() - 1
+                });
+                Ty::BoundVar(BoundVar::new(binders, idx))
+            }
+            _ => ty,
+        },
+        DebruijnIndex::INNERMOST,
+    );
+    let canonical = Canonical {
+        value: InEnvironment::new(goal.environment.clone(), value),
+        kinds: kinds.into(),
+    };
+    canonical_goal_to_chalk(db, canonical)
 }
 
 fn solve(
     db: &dyn HirDatabase,
     krate: CrateId,
-    goal: &chalk_ir::UCanonical<chalk_ir::InEnvironment<chalk_ir::Goal<Interner>>>,
+    goal: &ChalkGoal,
 ) -> Option<chalk_solve::Solution<Interner>> {
     let context = ChalkContext { db, krate };
     log::debug!("solve goal: {:?}", goal);
-    let mut solver = create_chalk_solver();
+    let mut solver = create_chalk_solver(db.solver_config());
 
     let fuel = std::cell::Cell::new(CHALK_SOLVER_FUEL);
 
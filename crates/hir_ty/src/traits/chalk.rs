@@ -4,7 +4,11 @@ use std::sync::Arc;
 use log::debug;
 
 use chalk_ir::{fold::shift::Shift, CanonicalVarKinds, GenericArg};
-use chalk_solve::rust_ir::{self, OpaqueTyDatumBound, WellKnownTrait};
+use chalk_solve::{
+    clauses::{builder::ClauseBuilder, program_clauses::ToProgramClauses},
+    rust_ir::{self, OpaqueTyDatumBound, WellKnownTrait},
+    RustIrDatabase,
+};
 
 use base_db::{salsa::InternKey, CrateId};
 use hir_def::{
@@ -16,30 +20,32 @@ use hir_expand::name::name;
 use super::ChalkContext;
 use crate::{
     db::HirDatabase,
-    display::HirDisplay,
     method_resolution::{TyFingerprint, ALL_FLOAT_FPS, ALL_INT_FPS},
-    utils::generics,
-    BoundVar, CallableDefId, CallableSig, DebruijnIndex, GenericPredicate, ProjectionPredicate,
-    ProjectionTy, Substs, TraitRef, Ty,
+    utils::{assoc_ty_trait, generics},
+    Binders, BoundVar, CallableDefId, CallableSig, DebruijnIndex, GenericPredicate,
+    ProjectionPredicate, ProjectionTy, Substs, TraitRef, Ty,
 };
-use mapping::{
-    convert_where_clauses, generic_predicate_to_inline_bound, make_binders, TypeAliasAsAssocType,
-    TypeAliasAsValue,
+use mapping::{associated_ty_bounds, make_binders, TypeAliasAsValue};
+
+#[cfg(any(test, feature = "fuzz"))]
+pub(crate) use self::mapping::TypeAliasAsForeignType;
+pub use self::mapping::{closure_ty, lookup_closure_ty, trait_id_from_chalk};
+pub(crate) use self::{
+    interner::*,
+    mapping::{assert_canonicalized, convert_where_clauses, TypeAliasAsAssocType},
 };
 
-pub(crate) use self::interner::*;
-
 pub(super) mod tls;
 mod interner;
 mod mapping;
 
-pub(super) trait ToChalk {
+pub(crate) trait ToChalk {
     type Chalk;
     fn to_chalk(self, db: &dyn HirDatabase) -> Self::Chalk;
     fn from_chalk(db: &dyn HirDatabase, chalk: Self::Chalk) -> Self;
 }
 
-pub(super) fn from_chalk<T, ChalkT>(db: &dyn HirDatabase, chalk: ChalkT) -> T
+pub(crate) fn from_chalk<T, ChalkT>(db: &dyn HirDatabase, chalk: ChalkT) -> T
 where
     T: ToChalk<Chalk = ChalkT>,
 {
@@ -178,20 +184,19 @@ impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
         let interned_id = crate::db::InternedOpaqueTyId::from(id);
         let full_id = self.db.lookup_intern_impl_trait_id(interned_id);
         let bound = match full_id {
-            crate::OpaqueTyId::ReturnTypeImplTrait(func, idx) => {
-                let datas = self
-                    .db
-                    .return_type_impl_traits(func)
-                    .expect("impl trait id without impl traits");
-                let data = &datas.value.impl_traits[idx as usize];
+            crate::OpaqueTyId::ReturnTypeImplTrait(..)
+            | crate::OpaqueTyId::TypeAliasImplTrait(..) => {
+                let datas =
+                    full_id.impl_trait_bounds(self.db).expect("impl trait id without impl traits");
                 let bound = OpaqueTyDatumBound {
                     bounds: make_binders(
-                        data.bounds
+                        datas
+                            .value
                             .value
                             .iter()
                             .cloned()
                             .filter(|b| !b.is_error())
-                            .map(|b| b.to_chalk(self.db))
+                            .filter_map(|b| b.to_chalk(self.db))
                             .collect(),
                         1,
                     ),
@@ -239,7 +244,10 @@ impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
                     });
                     let bound = OpaqueTyDatumBound {
                         bounds: make_binders(
-                            vec![impl_bound.to_chalk(self.db), proj_bound.to_chalk(self.db)],
+                            vec![impl_bound, proj_bound]
+                                .into_iter()
+                                .filter_map(|b| b.to_chalk(self.db))
+                                .collect(),
                             1,
                         ),
                         where_clauses: make_binders(vec![], 0),
@@ -374,34 +382,54 @@ pub(crate) fn program_clauses_for_chalk_env_query(
     chalk_solve::program_clauses_for_env(&ChalkContext { db, krate }, &environment)
 }
 
+/// Renders the program clauses for all trait impls in `krate`, as fed to
+/// Chalk. Only meant for debugging trait resolution.
+pub(crate) fn dump_chalk_program_query(db: &dyn HirDatabase, krate: CrateId) -> String {
+    dump_impl_clauses(db, krate, db.trait_impls_in_crate(krate).all_impls())
+}
+
+/// Renders the program clause for the trait impl `impl_id`, as fed to Chalk.
+/// Inherent impls and impls of unresolved traits don't have one, so they
+/// render as the empty string. Only meant for debugging trait resolution.
+pub(crate) fn dump_impl_clause_query(db: &dyn HirDatabase, impl_id: hir_def::ImplId) -> String {
+    if db.impl_trait_ref_chalk(impl_id).is_none() {
+        return String::new();
+    }
+    let krate = impl_id.lookup(db.upcast()).container.module(db.upcast()).krate();
+    dump_impl_clauses(db, krate, std::iter::once(impl_id))
+}
+
+fn dump_impl_clauses(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    impls: impl Iterator<Item = hir_def::ImplId>,
+) -> String {
+    let context = ChalkContext { db, krate };
+    let environment = chalk_ir::Environment::new(&Interner);
+    let mut clauses = Vec::new();
+    let mut builder = ClauseBuilder::new(&context, &mut clauses);
+    for impl_id in impls {
+        let impl_datum = context.impl_datum(impl_id.to_chalk(db));
+        impl_datum.to_program_clauses(&mut builder, &environment);
+    }
+    tls::set_current_program(db, || {
+        clauses.iter().map(|clause| format!("{:?}\n", clause)).collect()
+    })
+}
+
 pub(crate) fn associated_ty_data_query(
     db: &dyn HirDatabase,
     id: AssocTypeId,
 ) -> Arc<AssociatedTyDatum> {
     debug!("associated_ty_data {:?}", id);
     let type_alias: TypeAliasId = from_chalk::<TypeAliasAsAssocType, _>(db, id).0;
-    let trait_ = match type_alias.lookup(db.upcast()).container {
-        AssocContainerId::TraitId(t) => t,
-        _ => panic!("associated type not in trait"),
-    };
+    // we only ever hand associated types of traits to Chalk
+    let trait_ = assoc_ty_trait(db.upcast(), type_alias).expect("associated type not in trait");
 
-    // Lower bounds -- we could/should maybe move this to a separate query in `lower`
-    let type_alias_data = db.type_alias_data(type_alias);
     let generic_params = generics(db.upcast(), type_alias.into());
     let bound_vars = Substs::bound_vars(&generic_params, DebruijnIndex::INNERMOST);
-    let resolver = hir_def::resolver::HasResolver::resolver(type_alias, db.upcast());
-    let ctx = crate::TyLoweringContext::new(db, &resolver)
-        .with_type_param_mode(crate::lower::TypeParamLoweringMode::Variable);
-    let self_ty = Ty::BoundVar(crate::BoundVar::new(crate::DebruijnIndex::INNERMOST, 0));
-    let bounds = type_alias_data
-        .bounds
-        .iter()
-        .flat_map(|bound| GenericPredicate::from_type_bound(&ctx, bound, self_ty.clone()))
-        .filter_map(|pred| generic_predicate_to_inline_bound(db, &pred, &self_ty))
-        .map(|bound| make_binders(bound.shifted_in(&Interner), 0))
-        .collect();
-
-    let where_clauses = convert_where_clauses(db, type_alias.into(), &bound_vars);
+    let bounds = associated_ty_bounds(db, type_alias);
+    let where_clauses = convert_where_clauses(db, type_alias.into(), &bound_vars, None);
     let bound_data = rust_ir::AssociatedTyDatumBound { bounds, where_clauses };
     let datum = AssociatedTyDatum {
         trait_id: trait_.to_chalk(db),
@@ -432,7 +460,7 @@ pub(crate) fn trait_datum_query(
         marker: false,
         fundamental: false,
     };
-    let where_clauses = convert_where_clauses(db, trait_.into(), &bound_vars);
+    let where_clauses = convert_where_clauses(db, trait_.into(), &bound_vars, None);
     let associated_ty_ids = trait_data
         .associated_types()
         .map(|type_alias| TypeAliasAsAssocType(type_alias).to_chalk(db))
@@ -498,7 +526,7 @@ pub(crate) fn struct_datum_query(
         .map(|generic_def| {
             let generic_params = generics(db.upcast(), generic_def);
             let bound_vars = Substs::bound_vars(&generic_params, DebruijnIndex::INNERMOST);
-            convert_where_clauses(db, generic_def, &bound_vars)
+            convert_where_clauses(db, generic_def, &bound_vars, None)
         })
         .unwrap_or_else(Vec::new);
     let flags = rust_ir::AdtFlags {
@@ -540,7 +568,7 @@ fn impl_def_datum(
     impl_id: hir_def::ImplId,
 ) -> Arc<ImplDatum> {
     let trait_ref = db
-        .impl_trait(impl_id)
+        .impl_trait_ref_chalk(impl_id)
         // ImplIds for impls where the trait ref can't be resolved should never reach Chalk
         .expect("invalid impl passed to Chalk")
         .value;
@@ -548,22 +576,21 @@ fn impl_def_datum(
 
     let generic_params = generics(db.upcast(), impl_id.into());
     let bound_vars = Substs::bound_vars(&generic_params, DebruijnIndex::INNERMOST);
-    let trait_ = trait_ref.trait_;
+    let trait_: hir_def::TraitId = from_chalk(db, trait_ref.trait_id);
     let impl_type = if impl_id.lookup(db.upcast()).container.module(db.upcast()).krate() == krate {
         rust_ir::ImplType::Local
     } else {
         rust_ir::ImplType::External
     };
-    let where_clauses = convert_where_clauses(db, impl_id.into(), &bound_vars);
+    let where_clauses = convert_where_clauses(db, impl_id.into(), &bound_vars, None);
     let negative = impl_data.is_negative;
     debug!(
-        "impl {:?}: {}{} where {:?}",
+        "impl {:?}: {}{:?} where {:?}",
         chalk_id,
         if negative { "!" } else { "" },
-        trait_ref.display(db),
+        trait_ref,
         where_clauses
     );
-    let trait_ref = trait_ref.to_chalk(db);
 
     let polarity = if negative { rust_ir::Polarity::Negative } else { rust_ir::Polarity::Positive };
 
@@ -593,6 +620,14 @@ fn impl_def_datum(
     Arc::new(impl_datum)
 }
 
+pub(crate) fn impl_trait_ref_chalk_query(
+    db: &dyn HirDatabase,
+    impl_id: hir_def::ImplId,
+) -> Option<Binders<chalk_ir::TraitRef<Interner>>> {
+    let trait_ref = db.impl_trait(impl_id)?;
+    Some(Binders::new(trait_ref.num_binders, trait_ref.value.to_chalk(db)))
+}
+
 pub(crate) fn associated_ty_value_query(
     db: &dyn HirDatabase,
     krate: CrateId,
@@ -629,6 +664,20 @@ fn type_alias_associated_ty_value(
     Arc::new(value)
 }
 
+/// The parameter and return types of `def` converted to Chalk, bound by its
+/// generic parameters like in `callable_item_signature`.
+pub(crate) fn callable_sig_chalk_query(
+    db: &dyn HirDatabase,
+    def: CallableDefId,
+) -> Arc<Binders<FnDefInputsAndOutputDatum>> {
+    let sig = db.callable_item_signature(def);
+    let datum = rust_ir::FnDefInputsAndOutputDatum {
+        argument_types: sig.value.params().iter().map(|ty| ty.clone().to_chalk(db)).collect(),
+        return_type: sig.value.ret().clone().to_chalk(db),
+    };
+    Arc::new(Binders::new(sig.num_binders, datum))
+}
+
 pub(crate) fn fn_def_datum_query(
     db: &dyn HirDatabase,
     _krate: CrateId,
@@ -638,22 +687,11 @@ pub(crate) fn fn_def_datum_query(
     let generic_params = generics(db.upcast(), callable_def.into());
     let sig = db.callable_item_signature(callable_def);
     let bound_vars = Substs::bound_vars(&generic_params, DebruijnIndex::INNERMOST);
-    let where_clauses = convert_where_clauses(db, callable_def.into(), &bound_vars);
+    let where_clauses = convert_where_clauses(db, callable_def.into(), &bound_vars, None);
+    let inputs_and_output = db.callable_sig_chalk(callable_def);
     let bound = rust_ir::FnDefDatumBound {
         // Note: Chalk doesn't actually use this information yet as far as I am aware, but we provide it anyway
-        inputs_and_output: make_binders(
-            rust_ir::FnDefInputsAndOutputDatum {
-                argument_types: sig
-                    .value
-                    .params()
-                    .iter()
-                    .map(|ty| ty.clone().to_chalk(db))
-                    .collect(),
-                return_type: sig.value.ret().clone().to_chalk(db),
-            }
-            .shifted_in(&Interner),
-            0,
-        ),
+        inputs_and_output: make_binders(inputs_and_output.value.clone().shifted_in(&Interner), 0),
         where_clauses,
     };
     let datum = FnDefDatum {
@@ -687,11 +725,7 @@ pub(crate) fn adt_variance_query(
     adt_id: AdtId,
 ) -> Variances {
     let adt: crate::AdtId = from_chalk(db, adt_id);
-    let generic_params = generics(db.upcast(), adt.into());
-    Variances::from_iter(
-        &Interner,
-        std::iter::repeat(chalk_ir::Variance::Invariant).take(generic_params.len()),
-    )
+    Variances::from_iter(&Interner, db.adt_variances(adt).iter().map(|it| it.to_chalk(db)))
 }
 
 impl From<FnDefId> for crate::db::InternedCallableDefId {
@@ -23,6 +23,8 @@ pub(crate) type AssociatedTyValueId = chalk_solve::rust_ir::AssociatedTyValueId<
 pub(crate) type AssociatedTyValue = chalk_solve::rust_ir::AssociatedTyValue<Interner>;
 pub(crate) type FnDefId = chalk_ir::FnDefId<Interner>;
 pub(crate) type FnDefDatum = chalk_solve::rust_ir::FnDefDatum<Interner>;
+pub(crate) type FnDefInputsAndOutputDatum =
+    chalk_solve::rust_ir::FnDefInputsAndOutputDatum<Interner>;
 pub(crate) type OpaqueTyId = chalk_ir::OpaqueTyId<Interner>;
 pub(crate) type OpaqueTyDatum = chalk_solve::rust_ir::OpaqueTyDatum<Interner>;
 pub(crate) type Variances = chalk_ir::Variances<Interner>;
@@ -3,37 +3,70 @@
 //! Chalk (in both directions); plus some helper functions for more specialized
 //! conversions.
 
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
 use chalk_ir::{
-    cast::Cast, fold::shift::Shift, interner::HasInterner, LifetimeData, PlaceholderIndex,
-    UniverseIndex,
+    cast::Cast,
+    fold::{shift::Shift, Fold},
+    interner::HasInterner,
+    DebruijnIndex, LifetimeData, PlaceholderIndex, UniverseIndex,
 };
 use chalk_solve::rust_ir;
 
 use base_db::salsa::InternKey;
-use hir_def::{AssocContainerId, GenericDefId, Lookup, TypeAliasId};
+use hir_def::{expr::ExprId, DefWithBodyId, GenericDefId, TypeAliasId};
 
 use crate::{
     db::HirDatabase,
-    primitive::UintTy,
-    traits::{Canonical, Obligation},
-    AliasTy, CallableDefId, FnPointer, FnSig, GenericPredicate, InEnvironment, OpaqueTy,
-    OpaqueTyId, ProjectionPredicate, ProjectionTy, Scalar, Substs, TraitEnvironment, TraitRef, Ty,
+    primitive::{FloatTy, IntTy, UintTy},
+    traits::{Canonical, Obligation, SkipReason, SkippedPredicate},
+    utils::assoc_ty_trait,
+    AliasTy, CallableDefId, FnPointer, FnSig, GenericArg, GenericPredicate, InEnvironment,
+    Lifetime, Mutability, OpaqueTy, OpaqueTyId, OpaqueTyPredicate, ProjectionPredicate,
+    ProjectionTy, Safety, Scalar, Substs, TraitEnvironment, TraitRef, Ty, TypeWalk, VariableKind,
+    Variance,
 };
 
 use super::interner::*;
 use super::*;
 
+/// Enters a `tracing` span named `to_chalk` for the conversion of `$what` until
+/// the end of the enclosing block. Without the `chalk-timing` feature, this
+/// expands to nothing.
+macro_rules! to_chalk_span {
+    ($what:expr) => {
+        #[cfg(feature = "chalk-timing")]
+        let _span = tracing::trace_span!("to_chalk", what = $what).entered();
+    };
+}
+
 impl ToChalk for Ty {
     type Chalk = chalk_ir::Ty<Interner>;
     fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::Ty<Interner> {
+        to_chalk_span!("Ty");
         match self {
             Ty::Ref(m, parameters) => ref_to_chalk(db, m, parameters),
             Ty::Array(parameters) => array_to_chalk(db, parameters),
-            Ty::Function(FnPointer { sig: FnSig { variadic }, substs, .. }) => {
-                let substitution = chalk_ir::FnSubst(substs.to_chalk(db).shifted_in(&Interner));
+            Ty::Function(FnPointer { num_args, sig: FnSig { variadic, safety }, substs }) => {
+                debug_assert_eq!(
+                    substs.len(),
+                    num_args + 1,
+                    "fn pointer substs should be the parameters followed by the return type"
+                );
+                let substitution = substs.to_chalk(db).shifted_in(&Interner);
+                // the fn pointer's binder doesn't bind any (lifetime) variables
+                debug_assert_shifted_in(
+                    &substitution,
+                    DebruijnIndex::ONE,
+                    "fn pointer substitution",
+                );
+                let substitution = chalk_ir::FnSubst(substitution);
                 chalk_ir::TyKind::Function(chalk_ir::FnPointer {
                     num_binders: 0,
-                    sig: chalk_ir::FnSig { abi: (), safety: chalk_ir::Safety::Safe, variadic },
+                    sig: chalk_ir::FnSig { abi: (), safety: safety.to_chalk(db), variadic },
                     substitution,
                 })
                 .intern(&Interner)
@@ -51,24 +84,27 @@ impl ToChalk for Ty {
                 chalk_ir::TyKind::OpaqueType(id, substitution).intern(&Interner)
             }
 
+            // Foreign types can't be generic, so there's no substitution to
+            // convert. Generic parameters written on one anyway (which is an
+            // error) are dropped when lowering, see `type_for_type_alias`.
             Ty::ForeignType(type_alias) => {
                 let foreign_type = TypeAliasAsForeignType(type_alias);
                 let foreign_type_id = foreign_type.to_chalk(db);
                 chalk_ir::TyKind::Foreign(foreign_type_id).intern(&Interner)
             }
 
-            Ty::Scalar(scalar) => chalk_ir::TyKind::Scalar(scalar).intern(&Interner),
+            Ty::Scalar(scalar) => chalk_ir::TyKind::Scalar(scalar.to_chalk(db)).intern(&Interner),
 
             Ty::Tuple(cardinality, substs) => {
                 let substitution = substs.to_chalk(db);
                 chalk_ir::TyKind::Tuple(cardinality.into(), substitution).intern(&Interner)
             }
             Ty::Raw(mutability, substs) => {
-                let ty = substs[0].clone().to_chalk(db);
-                chalk_ir::TyKind::Raw(mutability, ty).intern(&Interner)
+                let ty = substs.get_or_unknown(0).to_chalk(db);
+                chalk_ir::TyKind::Raw(mutability.to_chalk(db), ty).intern(&Interner)
             }
             Ty::Slice(substs) => {
-                chalk_ir::TyKind::Slice(substs[0].clone().to_chalk(db)).intern(&Interner)
+                chalk_ir::TyKind::Slice(substs.get_or_unknown(0).to_chalk(db)).intern(&Interner)
             }
             Ty::Str => chalk_ir::TyKind::Str.intern(&Interner),
             Ty::FnDef(callable_def, substs) => {
@@ -107,18 +143,12 @@ impl ToChalk for Ty {
                 .to_ty::<Interner>(&Interner)
             }
             Ty::BoundVar(idx) => chalk_ir::TyKind::BoundVar(idx).intern(&Interner),
-            Ty::InferenceVar(..) => panic!("uncanonicalized infer ty"),
-            Ty::Dyn(predicates) => {
-                let where_clauses = chalk_ir::QuantifiedWhereClauses::from_iter(
-                    &Interner,
-                    predicates.iter().filter(|p| !p.is_error()).cloned().map(|p| p.to_chalk(db)),
-                );
-                let bounded_ty = chalk_ir::DynTy {
-                    bounds: make_binders(where_clauses, 1),
-                    lifetime: LifetimeData::Static.intern(&Interner),
-                };
-                chalk_ir::TyKind::Dyn(bounded_ty).intern(&Interner)
-            }
+            Ty::InferenceVar(var, kind) => panic!(
+                "uncanonicalized infer ty {:?} ({:?}) passed to `to_chalk`; \
+                 types need to be canonicalized before they're converted to Chalk",
+                var, kind
+            ),
+            Ty::Dyn(predicates) => dyn_to_chalk(db, &predicates, None),
             Ty::Alias(AliasTy::Opaque(opaque_ty)) => {
                 let opaque_ty_id = opaque_ty.opaque_ty_id.to_chalk(db);
                 let substitution = opaque_ty.parameters.to_chalk(db);
@@ -128,12 +158,18 @@ impl ToChalk for Ty {
                 }))
                 .intern(&Interner)
             }
-            Ty::Unknown => chalk_ir::TyKind::Error.intern(&Interner),
+            // Chalk has just the one error type, so `Ty::Unknown` comes back as
+            // `Ty::Error`
+            Ty::Unknown | Ty::Error => chalk_ir::TyKind::Error.intern(&Interner),
         }
     }
     fn from_chalk(db: &dyn HirDatabase, chalk: chalk_ir::Ty<Interner>) -> Self {
         match chalk.data(&Interner).kind.clone() {
-            chalk_ir::TyKind::Error => Ty::Unknown,
+            chalk_ir::TyKind::Error => Ty::Error,
+            // `Ty::Array` has no length yet, so the length is dropped whatever
+            // it is, including an inference variable. There's no known length
+            // to read back anyway: `TypeRef::Array` doesn't keep the length
+            // expression and our concrete consts are `()` (see `Interner`).
             chalk_ir::TyKind::Array(ty, _size) => Ty::Array(Substs::single(from_chalk(db, ty))),
             chalk_ir::TyKind::Placeholder(idx) => {
                 assert_eq!(idx.ui, UniverseIndex::ROOT);
@@ -155,7 +191,7 @@ impl ToChalk for Ty {
             }
             chalk_ir::TyKind::Function(chalk_ir::FnPointer {
                 num_binders,
-                sig: chalk_ir::FnSig { variadic, .. },
+                sig: chalk_ir::FnSig { variadic, safety, .. },
                 substitution,
                 ..
             }) => {
@@ -164,23 +200,29 @@ impl ToChalk for Ty {
                     db,
                     substitution.0.shifted_out(&Interner).expect("fn ptr should have no binders"),
                 );
-                Ty::Function(FnPointer {
-                    num_args: (substs.len() - 1),
-                    sig: FnSig { variadic },
-                    substs,
-                })
+                // The substitution holds the parameter types followed by the
+                // return type, so it can't be empty.
+                match substs.len().checked_sub(1) {
+                    Some(num_args) => {
+                        let sig = FnSig { variadic, safety: from_chalk(db, safety) };
+                        Ty::Function(FnPointer { num_args, sig, substs })
+                    }
+                    None => {
+                        log::warn!("fn pointer without a return type from Chalk");
+                        Ty::Error
+                    }
+                }
             }
             chalk_ir::TyKind::BoundVar(idx) => Ty::BoundVar(idx),
             chalk_ir::TyKind::InferenceVar(_iv, _kind) => Ty::Unknown,
             chalk_ir::TyKind::Dyn(where_clauses) => {
                 assert_eq!(where_clauses.bounds.binders.len(&Interner), 1);
-                let predicates = where_clauses
-                    .bounds
-                    .skip_binders()
-                    .iter(&Interner)
-                    .map(|c| from_chalk(db, c.clone()))
-                    .collect();
-                Ty::Dyn(predicates)
+                let mut predicates = where_clauses_from_chalk(
+                    db,
+                    where_clauses.bounds.skip_binders().as_slice(&Interner),
+                );
+                sort_dyn_predicates(db, &mut predicates);
+                Ty::Dyn(predicates.into())
             }
 
             chalk_ir::TyKind::Adt(struct_id, subst) => Ty::Adt(struct_id.0, from_chalk(db, subst)),
@@ -193,16 +235,30 @@ impl ToChalk for Ty {
                 Ty::OpaqueType(from_chalk(db, opaque_type_id), from_chalk(db, subst))
             }
 
-            chalk_ir::TyKind::Scalar(scalar) => Ty::Scalar(scalar),
+            chalk_ir::TyKind::Scalar(scalar) => Ty::Scalar(from_chalk(db, scalar)),
             chalk_ir::TyKind::Tuple(cardinality, subst) => {
-                Ty::Tuple(cardinality, from_chalk(db, subst))
+                let substs: Substs = from_chalk(db, subst);
+                let len = substs.len();
+                match tuple_substs_from_chalk(cardinality, substs) {
+                    Some(substs) => Ty::Tuple(cardinality, substs),
+                    None => {
+                        stdx::never!(
+                            "tuple of cardinality {} with {} types from Chalk",
+                            cardinality,
+                            len
+                        );
+                        Ty::Error
+                    }
+                }
             }
             chalk_ir::TyKind::Raw(mutability, ty) => {
-                Ty::Raw(mutability, Substs::single(from_chalk(db, ty)))
+                Ty::Raw(from_chalk(db, mutability), Substs::single(from_chalk(db, ty)))
             }
             chalk_ir::TyKind::Slice(ty) => Ty::Slice(Substs::single(from_chalk(db, ty))),
             chalk_ir::TyKind::Ref(mutability, _lifetime, ty) => {
-                Ty::Ref(mutability, Substs::single(from_chalk(db, ty)))
+                // `Ty::Ref` has no lifetime yet, so even a lifetime variable
+                // (which Chalk only makes up itself) is dropped here
+                Ty::Ref(from_chalk(db, mutability), Substs::single(from_chalk(db, ty)))
             }
             chalk_ir::TyKind::Str => Ty::Str,
             chalk_ir::TyKind::Never => Ty::Never,
@@ -212,37 +268,61 @@ impl ToChalk for Ty {
             }
 
             chalk_ir::TyKind::Closure(id, subst) => {
-                let id: crate::db::ClosureId = id.into();
-                let (def, expr) = db.lookup_intern_closure(id);
+                let (def, expr) = db.lookup_intern_closure(id.into());
                 Ty::Closure(def, expr, from_chalk(db, subst))
             }
 
+            // there's no substitution to read back, see `to_chalk`
             chalk_ir::TyKind::Foreign(foreign_def_id) => {
                 Ty::ForeignType(from_chalk::<TypeAliasAsForeignType, _>(db, foreign_def_id).0)
             }
-            chalk_ir::TyKind::Generator(_, _) => unimplemented!(), // FIXME
-            chalk_ir::TyKind::GeneratorWitness(_, _) => unimplemented!(), // FIXME
+            // we don't have generators, so Chalk only has the ones it's given
+            // by mistake
+            chalk_ir::TyKind::Generator(..) | chalk_ir::TyKind::GeneratorWitness(..) => {
+                log::warn!("generator type from Chalk");
+                Ty::Error
+            }
         }
     }
 }
 
+impl Ty {
+    /// Renders the type the way Chalk debug-prints it, with item names
+    /// resolved, e.g. `Vec<[?0 := Option<[?0 := Int(I32)]>]>`. For deeply
+    /// nested types this is easier to read than the derived `Debug` output.
+    /// This is only meant for debugging.
+    pub fn display_via_chalk(&self, db: &dyn HirDatabase) -> String {
+        let ty = self.clone().to_chalk(db);
+        super::tls::set_current_program(db, || format!("{:?}", ty))
+    }
+}
+
+/// Checks the element types of a tuple of `cardinality` coming from Chalk,
+/// returning `None` if Chalk gave us a different number of types.
+fn tuple_substs_from_chalk(cardinality: usize, substs: Substs) -> Option<Substs> {
+    if substs.len() != cardinality {
+        return None;
+    }
+    Some(substs)
+}
+
 /// We currently don't model lifetimes, but Chalk does. So, we have to insert a
 /// fake lifetime here, because Chalks built-in logic may expect it to be there.
 fn ref_to_chalk(
     db: &dyn HirDatabase,
-    mutability: chalk_ir::Mutability,
+    mutability: Mutability,
     subst: Substs,
 ) -> chalk_ir::Ty<Interner> {
-    let arg = subst[0].clone().to_chalk(db);
-    let lifetime = LifetimeData::Static.intern(&Interner);
-    chalk_ir::TyKind::Ref(mutability, lifetime, arg).intern(&Interner)
+    let arg = subst.get_or_unknown(0).to_chalk(db);
+    let lifetime = Lifetime::Static.to_chalk(db);
+    chalk_ir::TyKind::Ref(mutability.to_chalk(db), lifetime, arg).intern(&Interner)
 }
 
 /// We currently don't model constants, but Chalk does. So, we have to insert a
 /// fake constant here, because Chalks built-in logic may expect it to be there.
 fn array_to_chalk(db: &dyn HirDatabase, subst: Substs) -> chalk_ir::Ty<Interner> {
-    let arg = subst[0].clone().to_chalk(db);
-    let usize_ty = chalk_ir::TyKind::Scalar(Scalar::Uint(UintTy::Usize)).intern(&Interner);
+    let arg = subst.get_or_unknown(0).to_chalk(db);
+    let usize_ty = Ty::Scalar(Scalar::Uint(UintTy::Usize)).to_chalk(db);
     let const_ = chalk_ir::ConstData {
         ty: usize_ty,
         value: chalk_ir::ConstValue::Concrete(chalk_ir::ConcreteConst { interned: () }),
@@ -251,19 +331,254 @@ fn array_to_chalk(db: &dyn HirDatabase, subst: Substs) -> chalk_ir::Ty<Interner>
     chalk_ir::TyKind::Array(arg, const_).intern(&Interner)
 }
 
+/// Converts the bounds of a `dyn` type, leaving out (and recording in
+/// `skipped`) the ones that errored.
+fn dyn_to_chalk(
+    db: &dyn HirDatabase,
+    predicates: &[GenericPredicate],
+    mut skipped: Option<&mut Vec<SkippedPredicate>>,
+) -> chalk_ir::Ty<Interner> {
+    let mut predicates = predicates
+        .iter()
+        .filter(|pred| !skip_if_error(pred, SkipReason::ErrorInDynBound, &mut skipped))
+        .cloned()
+        .collect::<Vec<_>>();
+    if predicates.is_empty() {
+        // every bound errored; a `dyn` without bounds isn't a type
+        return chalk_ir::TyKind::Error.intern(&Interner);
+    }
+    sort_dyn_predicates(db, &mut predicates);
+    let where_clauses = chalk_ir::QuantifiedWhereClauses::from_iter(
+        &Interner,
+        predicates.into_iter().filter_map(|p| p.to_chalk(db)),
+    );
+    let bounded_ty = chalk_ir::DynTy {
+        bounds: make_binders(where_clauses, 1),
+        lifetime: Lifetime::Static.to_chalk(db),
+    };
+    chalk_ir::TyKind::Dyn(bounded_ty).intern(&Interner)
+}
+
+/// Returns whether `pred` is an error, which we don't pass on to Chalk. If so,
+/// it's recorded in `skipped` along with `reason`.
+fn skip_if_error(
+    pred: &GenericPredicate,
+    reason: SkipReason,
+    skipped: &mut Option<&mut Vec<SkippedPredicate>>,
+) -> bool {
+    if !pred.is_error() {
+        return false;
+    }
+    if let Some(skipped) = skipped {
+        skipped.push(SkippedPredicate { predicate: pred.clone(), reason });
+    }
+    true
+}
+
+/// Builds the type of the closure `expr` in `def`. This takes care of
+/// interning the closure, so the type can be converted to Chalk without
+/// callers dealing with `ClosureId`s.
+pub fn closure_ty(db: &dyn HirDatabase, def: DefWithBodyId, expr: ExprId, substs: Substs) -> Ty {
+    db.intern_closure((def, expr));
+    Ty::Closure(def, expr, substs)
+}
+
+/// The inverse of [`closure_ty`]: if the Chalk type `ty` is a closure type,
+/// returns it as a `Ty::Closure`. Panics if its closure id wasn't interned in
+/// `db`, see `OpaqueTyId::from_chalk`.
+pub fn lookup_closure_ty(db: &dyn HirDatabase, ty: &chalk_ir::Ty<Interner>) -> Option<Ty> {
+    match ty.kind(&Interner) {
+        chalk_ir::TyKind::Closure(..) => Some(from_chalk(db, ty.clone())),
+        _ => None,
+    }
+}
+
+/// Our primitive types mirror Chalk's, but they're our own so they can
+/// diverge, e.g. to render `usize` with the target's width. Each width maps to
+/// itself, so the pointer-sized `usize` and `isize` are never conflated with
+/// the fixed-width integer types.
+impl ToChalk for Scalar {
+    type Chalk = chalk_ir::Scalar;
+
+    fn to_chalk(self, _db: &dyn HirDatabase) -> chalk_ir::Scalar {
+        match self {
+            Scalar::Bool => chalk_ir::Scalar::Bool,
+            Scalar::Char => chalk_ir::Scalar::Char,
+            Scalar::Int(int_ty) => chalk_ir::Scalar::Int(match int_ty {
+                IntTy::Isize => chalk_ir::IntTy::Isize,
+                IntTy::I8 => chalk_ir::IntTy::I8,
+                IntTy::I16 => chalk_ir::IntTy::I16,
+                IntTy::I32 => chalk_ir::IntTy::I32,
+                IntTy::I64 => chalk_ir::IntTy::I64,
+                IntTy::I128 => chalk_ir::IntTy::I128,
+            }),
+            Scalar::Uint(uint_ty) => chalk_ir::Scalar::Uint(match uint_ty {
+                UintTy::Usize => chalk_ir::UintTy::Usize,
+                UintTy::U8 => chalk_ir::UintTy::U8,
+                UintTy::U16 => chalk_ir::UintTy::U16,
+                UintTy::U32 => chalk_ir::UintTy::U32,
+                UintTy::U64 => chalk_ir::UintTy::U64,
+                UintTy::U128 => chalk_ir::UintTy::U128,
+            }),
+            Scalar::Float(float_ty) => chalk_ir::Scalar::Float(match float_ty {
+                FloatTy::F32 => chalk_ir::FloatTy::F32,
+                FloatTy::F64 => chalk_ir::FloatTy::F64,
+            }),
+        }
+    }
+
+    fn from_chalk(_db: &dyn HirDatabase, scalar: chalk_ir::Scalar) -> Scalar {
+        match scalar {
+            chalk_ir::Scalar::Bool => Scalar::Bool,
+            chalk_ir::Scalar::Char => Scalar::Char,
+            chalk_ir::Scalar::Int(int_ty) => Scalar::Int(match int_ty {
+                chalk_ir::IntTy::Isize => IntTy::Isize,
+                chalk_ir::IntTy::I8 => IntTy::I8,
+                chalk_ir::IntTy::I16 => IntTy::I16,
+                chalk_ir::IntTy::I32 => IntTy::I32,
+                chalk_ir::IntTy::I64 => IntTy::I64,
+                chalk_ir::IntTy::I128 => IntTy::I128,
+            }),
+            chalk_ir::Scalar::Uint(uint_ty) => Scalar::Uint(match uint_ty {
+                chalk_ir::UintTy::Usize => UintTy::Usize,
+                chalk_ir::UintTy::U8 => UintTy::U8,
+                chalk_ir::UintTy::U16 => UintTy::U16,
+                chalk_ir::UintTy::U32 => UintTy::U32,
+                chalk_ir::UintTy::U64 => UintTy::U64,
+                chalk_ir::UintTy::U128 => UintTy::U128,
+            }),
+            chalk_ir::Scalar::Float(float_ty) => Scalar::Float(match float_ty {
+                chalk_ir::FloatTy::F32 => FloatTy::F32,
+                chalk_ir::FloatTy::F64 => FloatTy::F64,
+            }),
+        }
+    }
+}
+
+impl ToChalk for Mutability {
+    type Chalk = chalk_ir::Mutability;
+
+    fn to_chalk(self, _db: &dyn HirDatabase) -> chalk_ir::Mutability {
+        match self {
+            Mutability::Not => chalk_ir::Mutability::Not,
+            Mutability::Mut => chalk_ir::Mutability::Mut,
+        }
+    }
+
+    fn from_chalk(_db: &dyn HirDatabase, mutability: chalk_ir::Mutability) -> Mutability {
+        match mutability {
+            chalk_ir::Mutability::Not => Mutability::Not,
+            chalk_ir::Mutability::Mut => Mutability::Mut,
+        }
+    }
+}
+
+impl ToChalk for Variance {
+    type Chalk = chalk_ir::Variance;
+
+    fn to_chalk(self, _db: &dyn HirDatabase) -> chalk_ir::Variance {
+        match self {
+            Variance::Covariant => chalk_ir::Variance::Covariant,
+            Variance::Invariant => chalk_ir::Variance::Invariant,
+            Variance::Contravariant => chalk_ir::Variance::Contravariant,
+        }
+    }
+
+    fn from_chalk(_db: &dyn HirDatabase, variance: chalk_ir::Variance) -> Variance {
+        match variance {
+            chalk_ir::Variance::Covariant => Variance::Covariant,
+            chalk_ir::Variance::Invariant => Variance::Invariant,
+            chalk_ir::Variance::Contravariant => Variance::Contravariant,
+        }
+    }
+}
+
+impl ToChalk for Safety {
+    type Chalk = chalk_ir::Safety;
+
+    fn to_chalk(self, _db: &dyn HirDatabase) -> chalk_ir::Safety {
+        match self {
+            Safety::Safe => chalk_ir::Safety::Safe,
+            Safety::Unsafe => chalk_ir::Safety::Unsafe,
+        }
+    }
+
+    fn from_chalk(_db: &dyn HirDatabase, safety: chalk_ir::Safety) -> Safety {
+        match safety {
+            chalk_ir::Safety::Safe => Safety::Safe,
+            chalk_ir::Safety::Unsafe => Safety::Unsafe,
+        }
+    }
+}
+
+impl ToChalk for Lifetime {
+    type Chalk = chalk_ir::Lifetime<Interner>;
+
+    /// Lifetime parameters aren't passed to Chalk yet, so they're erased.
+    fn to_chalk(self, _db: &dyn HirDatabase) -> chalk_ir::Lifetime<Interner> {
+        match self {
+            Lifetime::Static => LifetimeData::Static.intern(&Interner),
+            Lifetime::Parameter(_) | Lifetime::Erased => LifetimeData::Erased.intern(&Interner),
+        }
+    }
+
+    /// `'static` stays `'static`; everything else (including variables,
+    /// placeholders and the empty lifetime) becomes `Lifetime::Erased`, since
+    /// we have nothing to map it to.
+    fn from_chalk(_db: &dyn HirDatabase, lifetime: chalk_ir::Lifetime<Interner>) -> Lifetime {
+        match lifetime.data(&Interner) {
+            LifetimeData::Static => Lifetime::Static,
+            LifetimeData::BoundVar(_)
+            | LifetimeData::InferenceVar(_)
+            | LifetimeData::Placeholder(_)
+            | LifetimeData::Empty(_)
+            | LifetimeData::Erased => Lifetime::Erased,
+            LifetimeData::Phantom(void, _) => match *void {},
+        }
+    }
+}
+
+impl ToChalk for GenericArg {
+    type Chalk = chalk_ir::GenericArg<Interner>;
+
+    fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::GenericArg<Interner> {
+        match self {
+            GenericArg::Ty(ty) => ty.to_chalk(db).cast(&Interner),
+        }
+    }
+
+    fn from_chalk(db: &dyn HirDatabase, arg: chalk_ir::GenericArg<Interner>) -> GenericArg {
+        match arg.data(&Interner) {
+            chalk_ir::GenericArgData::Ty(ty) => GenericArg::Ty(from_chalk(db, ty.clone())),
+            // lifetimes and consts are erased when lowering, so there's
+            // nothing to read back
+            chalk_ir::GenericArgData::Lifetime(_) | chalk_ir::GenericArgData::Const(_) => {
+                GenericArg::Ty(Ty::Unknown)
+            }
+        }
+    }
+}
+
 impl ToChalk for Substs {
     type Chalk = chalk_ir::Substitution<Interner>;
 
     fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::Substitution<Interner> {
-        chalk_ir::Substitution::from_iter(&Interner, self.iter().map(|ty| ty.clone().to_chalk(db)))
+        to_chalk_span!("Substs");
+        if self.is_empty() {
+            // most types aren't generic, so this is a hot path
+            return chalk_ir::Substitution::empty(&Interner);
+        }
+        chalk_ir::Substitution::from_iter(
+            &Interner,
+            self.iter().map(|ty| GenericArg::Ty(ty.clone()).to_chalk(db)),
+        )
     }
 
     fn from_chalk(db: &dyn HirDatabase, parameters: chalk_ir::Substitution<Interner>) -> Substs {
         let tys = parameters
             .iter(&Interner)
-            .map(|p| match p.ty(&Interner) {
-                Some(ty) => from_chalk(db, ty.clone()),
-                None => unimplemented!(),
+            .map(|p| match from_chalk(db, p.clone()) {
+                GenericArg::Ty(ty) => ty,
             })
             .collect();
         Substs(tys)
@@ -294,10 +609,16 @@ impl ToChalk for hir_def::TraitId {
     }
 
     fn from_chalk(_db: &dyn HirDatabase, trait_id: TraitId) -> hir_def::TraitId {
-        InternKey::from_intern_id(trait_id.0)
+        trait_id_from_chalk(trait_id)
     }
 }
 
+/// Converts a Chalk trait id back to ours. Unlike most conversions, this
+/// doesn't need a database, since both are the same intern key.
+pub fn trait_id_from_chalk(trait_id: TraitId) -> hir_def::TraitId {
+    InternKey::from_intern_id(trait_id.0)
+}
+
 impl ToChalk for OpaqueTyId {
     type Chalk = chalk_ir::OpaqueTyId<Interner>;
 
@@ -305,6 +626,9 @@ impl ToChalk for OpaqueTyId {
         db.intern_impl_trait_id(self).into()
     }
 
+    /// Like the other interned ids, this panics inside salsa if `opaque_ty_id`
+    /// wasn't interned in `db`: salsa has no fallible lookup, and catching the
+    /// panic would swallow cancellation.
     fn from_chalk(
         db: &dyn HirDatabase,
         opaque_ty_id: chalk_ir::OpaqueTyId<Interner>,
@@ -344,6 +668,8 @@ impl ToChalk for CallableDefId {
         db.intern_callable_def(self).into()
     }
 
+    /// Panics inside salsa if `fn_def_id` wasn't interned in `db`, see
+    /// `OpaqueTyId::from_chalk`.
     fn from_chalk(db: &dyn HirDatabase, fn_def_id: FnDefId) -> CallableDefId {
         db.lookup_intern_callable_def(fn_def_id.into())
     }
@@ -390,66 +716,192 @@ impl ToChalk for TypeAliasAsValue {
         _db: &dyn HirDatabase,
         assoc_ty_value_id: AssociatedTyValueId,
     ) -> TypeAliasAsValue {
-        TypeAliasAsValue(TypeAliasId::from_intern_id(assoc_ty_value_id.0))
+        TypeAliasAsValue(InternKey::from_intern_id(assoc_ty_value_id.0))
     }
 }
 
+/// `GenericPredicate::Error` has no Chalk equivalent, so it maps to `None`.
+/// Callers are expected to filter out errors before converting; if one slips
+/// through anyway, we skip it with a warning instead of bringing down the
+/// whole server.
 impl ToChalk for GenericPredicate {
-    type Chalk = chalk_ir::QuantifiedWhereClause<Interner>;
+    type Chalk = Option<chalk_ir::QuantifiedWhereClause<Interner>>;
 
-    fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::QuantifiedWhereClause<Interner> {
-        match self {
-            GenericPredicate::Implemented(trait_ref) => {
-                let chalk_trait_ref = trait_ref.to_chalk(db);
-                let chalk_trait_ref = chalk_trait_ref.shifted_in(&Interner);
-                make_binders(chalk_ir::WhereClause::Implemented(chalk_trait_ref), 0)
-            }
-            GenericPredicate::Projection(projection_pred) => {
-                let ty = projection_pred.ty.to_chalk(db).shifted_in(&Interner);
-                let projection = projection_pred.projection_ty.to_chalk(db).shifted_in(&Interner);
-                let alias = chalk_ir::AliasTy::Projection(projection);
-                make_binders(chalk_ir::WhereClause::AliasEq(chalk_ir::AliasEq { alias, ty }), 0)
-            }
-            GenericPredicate::Error => panic!("tried passing GenericPredicate::Error to Chalk"),
-        }
+    fn to_chalk(self, db: &dyn HirDatabase) -> Option<chalk_ir::QuantifiedWhereClause<Interner>> {
+        generic_predicate_to_chalk_at(db, self, DebruijnIndex::INNERMOST)
     }
 
     fn from_chalk(
         db: &dyn HirDatabase,
-        where_clause: chalk_ir::QuantifiedWhereClause<Interner>,
+        where_clause: Option<chalk_ir::QuantifiedWhereClause<Interner>>,
     ) -> GenericPredicate {
-        // we don't produce any where clauses with binders and can't currently deal with them
-        match where_clause
-            .skip_binders()
-            .clone()
-            .shifted_out(&Interner)
-            .expect("unexpected bound vars in where clause")
-        {
-            chalk_ir::WhereClause::Implemented(tr) => {
-                GenericPredicate::Implemented(from_chalk(db, tr))
-            }
-            chalk_ir::WhereClause::AliasEq(projection_eq) => {
-                let projection_ty = from_chalk(
-                    db,
-                    match projection_eq.alias {
-                        chalk_ir::AliasTy::Projection(p) => p,
-                        _ => unimplemented!(),
-                    },
-                );
-                let ty = from_chalk(db, projection_eq.ty);
-                GenericPredicate::Projection(ProjectionPredicate { projection_ty, ty })
-            }
+        generic_predicate_from_chalk_at(db, where_clause, DebruijnIndex::INNERMOST)
+    }
+}
 
-            chalk_ir::WhereClause::LifetimeOutlives(_) => {
-                // we shouldn't get these from Chalk
-                panic!("encountered LifetimeOutlives from Chalk")
+/// Converts a predicate whose bound variables are bound `depth` binder levels
+/// further out than the where clause that's produced. The where clause always
+/// adds a binder level of its own on top of that.
+pub(super) fn generic_predicate_to_chalk_at(
+    db: &dyn HirDatabase,
+    pred: GenericPredicate,
+    depth: DebruijnIndex,
+) -> Option<chalk_ir::QuantifiedWhereClause<Interner>> {
+    let shift = depth.shifted_in();
+    let where_clause = match pred {
+        GenericPredicate::Implemented(trait_ref) if trait_ref.is_error() => {
+            log::warn!("tried passing an error trait ref to Chalk, skipping it");
+            return None;
+        }
+        GenericPredicate::Implemented(trait_ref) => {
+            let chalk_trait_ref = trait_ref.to_chalk(db).shifted_in_from(&Interner, shift);
+            make_binders(chalk_ir::WhereClause::Implemented(chalk_trait_ref), 0)
+        }
+        GenericPredicate::Projection(projection_pred) => {
+            let ty = projection_pred.ty.to_chalk(db).shifted_in_from(&Interner, shift);
+            let projection =
+                projection_pred.projection_ty.to_chalk(db).shifted_in_from(&Interner, shift);
+            let alias = chalk_ir::AliasTy::Projection(projection);
+            make_binders(chalk_ir::WhereClause::AliasEq(chalk_ir::AliasEq { alias, ty }), 0)
+        }
+        GenericPredicate::Opaque(opaque_pred) => {
+            let OpaqueTy { opaque_ty_id, parameters } = opaque_pred.opaque_ty;
+            let opaque_ty = chalk_ir::OpaqueTy {
+                opaque_ty_id: opaque_ty_id.to_chalk(db),
+                substitution: parameters.to_chalk(db),
+            };
+            let alias_eq = chalk_ir::AliasEq {
+                alias: chalk_ir::AliasTy::Opaque(opaque_ty),
+                ty: opaque_pred.ty.to_chalk(db),
+            };
+            let alias_eq = alias_eq.shifted_in_from(&Interner, shift);
+            make_binders(chalk_ir::WhereClause::AliasEq(alias_eq), 0)
+        }
+        GenericPredicate::Error => {
+            log::warn!("tried passing GenericPredicate::Error to Chalk, skipping it");
+            return None;
+        }
+    };
+    Some(where_clause)
+}
+
+/// The inverse of `generic_predicate_to_chalk_at`. Where clauses we can't
+/// represent, like `for<'a> T: Foo<'a>` or ones read back at the wrong depth,
+/// become `GenericPredicate::Error`.
+pub(super) fn generic_predicate_from_chalk_at(
+    db: &dyn HirDatabase,
+    where_clause: Option<chalk_ir::QuantifiedWhereClause<Interner>>,
+    depth: DebruijnIndex,
+) -> GenericPredicate {
+    let where_clause = match where_clause {
+        Some(it) => it,
+        None => return GenericPredicate::Error,
+    };
+    match try_generic_predicate_from_chalk_at(db, where_clause, depth) {
+        Ok(pred) => pred,
+        Err(err) => {
+            log::warn!("unrepresentable where clause from Chalk: {}", err);
+            GenericPredicate::Error
+        }
+    }
+}
+
+/// Converts where clauses we get back from Chalk, dropping the ones we can't
+/// represent as a `GenericPredicate` instead of panicking on them.
+pub(super) fn where_clauses_from_chalk(
+    db: &dyn HirDatabase,
+    clauses: &[chalk_ir::QuantifiedWhereClause<Interner>],
+) -> Vec<GenericPredicate> {
+    clauses
+        .iter()
+        .filter_map(|clause| {
+            match try_generic_predicate_from_chalk_at(db, clause.clone(), DebruijnIndex::INNERMOST)
+            {
+                Ok(pred) => Some(pred),
+                Err(err) => {
+                    log::warn!("dropping where clause from Chalk: {}", err);
+                    None
+                }
             }
+        })
+        .collect()
+}
+
+/// Sorts the predicates of a `dyn` type, so that `dyn A + B` and `dyn B + A`
+/// convert the same way: the principal trait comes first (`Ty::dyn_trait_ref`
+/// relies on that), then its associated type bindings and then the auto traits,
+/// each ordered by id. Valid code has no two predicates with the same key, so
+/// the order never depends on the substitutions.
+fn sort_dyn_predicates(db: &dyn HirDatabase, predicates: &mut [GenericPredicate]) {
+    predicates.sort_by_key(|pred| match pred {
+        GenericPredicate::Implemented(trait_ref) => {
+            let rank = if db.trait_data(trait_ref.trait_).auto { 2 } else { 0 };
+            (rank, Some(trait_ref.trait_.as_intern_id()))
+        }
+        GenericPredicate::Projection(proj) => {
+            (1, Some(proj.projection_ty.associated_ty.as_intern_id()))
+        }
+        GenericPredicate::Opaque(_) | GenericPredicate::Error => (3, None),
+    });
+}
 
-            chalk_ir::WhereClause::TypeOutlives(_) => {
-                // we shouldn't get these from Chalk
-                panic!("encountered TypeOutlives from Chalk")
+fn try_generic_predicate_from_chalk_at(
+    db: &dyn HirDatabase,
+    where_clause: chalk_ir::QuantifiedWhereClause<Interner>,
+    depth: DebruijnIndex,
+) -> Result<GenericPredicate, &'static str> {
+    let shift = depth.shifted_in();
+    // we don't produce any where clauses with binders and can't currently deal with them
+    let where_clause = where_clause
+        .skip_binders()
+        .clone()
+        .shifted_out_to(&Interner, shift)
+        .map_err(|_| "unexpected bound vars in where clause")?;
+    let pred = match where_clause {
+        chalk_ir::WhereClause::Implemented(tr) => GenericPredicate::Implemented(from_chalk(db, tr)),
+        chalk_ir::WhereClause::AliasEq(alias_eq) => {
+            let ty = from_chalk(db, alias_eq.ty);
+            match alias_eq.alias {
+                chalk_ir::AliasTy::Projection(p) => {
+                    let projection_ty = from_chalk(db, p);
+                    GenericPredicate::Projection(ProjectionPredicate { projection_ty, ty })
+                }
+                chalk_ir::AliasTy::Opaque(opaque_ty) => {
+                    let opaque_ty_id = from_chalk(db, opaque_ty.opaque_ty_id);
+                    let parameters = from_chalk(db, opaque_ty.substitution);
+                    let opaque_ty = OpaqueTy { opaque_ty_id, parameters };
+                    GenericPredicate::Opaque(OpaqueTyPredicate { opaque_ty, ty })
+                }
             }
         }
+
+        // we shouldn't get these from Chalk
+        chalk_ir::WhereClause::LifetimeOutlives(_) => {
+            return Err("encountered LifetimeOutlives from Chalk")
+        }
+        chalk_ir::WhereClause::TypeOutlives(_) => {
+            return Err("encountered TypeOutlives from Chalk")
+        }
+    };
+    Ok(pred)
+}
+
+/// Checks (in debug builds) that `value`, which was shifted in by `shift`
+/// binder levels binding no variables, doesn't refer to those levels. If it
+/// does, it was shifted by too little and its variables got captured.
+#[track_caller]
+fn debug_assert_shifted_in<T>(value: &T, shift: DebruijnIndex, what: &str)
+where
+    T: Fold<Interner, Result = T> + Clone + fmt::Debug,
+{
+    if cfg!(debug_assertions) {
+        assert!(
+            value.clone().shifted_out_to(&Interner, shift).is_ok(),
+            "{} is mis-shifted, expected {:?} binder level(s): {:?}",
+            what,
+            shift.depth(),
+            value
+        );
     }
 }
 
@@ -488,8 +940,14 @@ impl ToChalk for ProjectionPredicate {
         }
     }
 
-    fn from_chalk(_db: &dyn HirDatabase, _normalize: chalk_ir::AliasEq<Interner>) -> Self {
-        unimplemented!()
+    fn from_chalk(db: &dyn HirDatabase, normalize: chalk_ir::AliasEq<Interner>) -> Self {
+        let projection_ty = match normalize.alias {
+            chalk_ir::AliasTy::Projection(p) => from_chalk(db, p),
+            chalk_ir::AliasTy::Opaque(_) => {
+                panic!("encountered opaque type alias equality from Chalk")
+            }
+        };
+        ProjectionPredicate { projection_ty, ty: from_chalk(db, normalize.ty) }
     }
 }
 
@@ -500,11 +958,25 @@ impl ToChalk for Obligation {
         match self {
             Obligation::Trait(tr) => tr.to_chalk(db).cast(&Interner),
             Obligation::Projection(pr) => pr.to_chalk(db).cast(&Interner),
+            Obligation::WellFormed(ty) => {
+                chalk_ir::DomainGoal::WellFormed(chalk_ir::WellFormed::Ty(ty.to_chalk(db)))
+            }
         }
     }
 
-    fn from_chalk(_db: &dyn HirDatabase, _goal: chalk_ir::DomainGoal<Interner>) -> Self {
-        unimplemented!()
+    fn from_chalk(db: &dyn HirDatabase, goal: chalk_ir::DomainGoal<Interner>) -> Self {
+        match goal {
+            chalk_ir::DomainGoal::Holds(chalk_ir::WhereClause::Implemented(tr)) => {
+                Obligation::Trait(from_chalk(db, tr))
+            }
+            chalk_ir::DomainGoal::Holds(chalk_ir::WhereClause::AliasEq(alias_eq)) => {
+                Obligation::Projection(from_chalk(db, alias_eq))
+            }
+            chalk_ir::DomainGoal::WellFormed(chalk_ir::WellFormed::Ty(ty)) => {
+                Obligation::WellFormed(from_chalk(db, ty))
+            }
+            goal => panic!("goal {:?} is not an obligation", goal),
+        }
     }
 }
 
@@ -516,11 +988,12 @@ where
     type Chalk = chalk_ir::Canonical<T::Chalk>;
 
     fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::Canonical<T::Chalk> {
-        let kinds = self.kinds.iter().map(|&tk| {
-            chalk_ir::CanonicalVarKind::new(
-                chalk_ir::VariableKind::Ty(tk),
-                chalk_ir::UniverseIndex::ROOT,
-            )
+        let kinds = self.kinds.iter().map(|kind| {
+            let kind = match kind {
+                VariableKind::Ty(tk) => chalk_ir::VariableKind::Ty(*tk),
+                VariableKind::Const(ty) => chalk_ir::VariableKind::Const(ty.clone().to_chalk(db)),
+            };
+            chalk_ir::CanonicalVarKind::new(kind, chalk_ir::UniverseIndex::ROOT)
         });
         let value = self.value.to_chalk(db);
         chalk_ir::Canonical {
@@ -533,44 +1006,99 @@ where
         let kinds = canonical
             .binders
             .iter(&Interner)
-            .map(|k| match k.kind {
-                chalk_ir::VariableKind::Ty(tk) => tk,
+            .map(|k| match &k.kind {
+                chalk_ir::VariableKind::Ty(tk) => VariableKind::Ty(*tk),
                 // HACK: Chalk can sometimes return new lifetime variables. We
                 // want to just skip them, but to not mess up the indices of
                 // other variables, we'll just create a new type variable in
                 // their place instead. This should not matter (we never see the
                 // actual *uses* of the lifetime variable).
-                chalk_ir::VariableKind::Lifetime => chalk_ir::TyVariableKind::General,
-                chalk_ir::VariableKind::Const(_) => panic!("unexpected const from Chalk"),
+                chalk_ir::VariableKind::Lifetime => {
+                    VariableKind::Ty(chalk_ir::TyVariableKind::General)
+                }
+                chalk_ir::VariableKind::Const(ty) => {
+                    VariableKind::Const(from_chalk(db, ty.clone()))
+                }
             })
             .collect();
         Canonical { kinds, value: from_chalk(db, canonical.value) }
     }
 }
 
+/// A predicate assumed to hold in an environment, which Chalk represents as a
+/// `FromEnv` program clause.
+pub(super) struct FromEnvClause(pub(super) GenericPredicate);
+
+impl ToChalk for FromEnvClause {
+    type Chalk = Option<chalk_ir::ProgramClause<Interner>>;
+
+    fn to_chalk(self, db: &dyn HirDatabase) -> Option<chalk_ir::ProgramClause<Interner>> {
+        let where_clause = self.0.to_chalk(db)?;
+        let program_clause: chalk_ir::ProgramClause<Interner> = where_clause.cast(&Interner);
+        Some(program_clause.into_from_env_clause(&Interner))
+    }
+
+    fn from_chalk(
+        db: &dyn HirDatabase,
+        clause: Option<chalk_ir::ProgramClause<Interner>>,
+    ) -> FromEnvClause {
+        // `into_from_env_clause` only turns `Implemented` into `FromEnv`, other
+        // where clauses stay as they are
+        let where_clause = clause.and_then(|clause| {
+            let implication = &clause.data(&Interner).0;
+            if !implication.skip_binders().conditions.is_empty(&Interner) {
+                return None;
+            }
+            let where_clause = match &implication.skip_binders().consequence {
+                chalk_ir::DomainGoal::FromEnv(chalk_ir::FromEnv::Trait(trait_ref)) => {
+                    chalk_ir::WhereClause::Implemented(trait_ref.clone())
+                }
+                chalk_ir::DomainGoal::Holds(where_clause) => where_clause.clone(),
+                _ => return None,
+            };
+            Some(make_binders_from_kinds(where_clause, implication.binders.clone()))
+        });
+        FromEnvClause(from_chalk(db, where_clause))
+    }
+}
+
 impl ToChalk for Arc<TraitEnvironment> {
     type Chalk = chalk_ir::Environment<Interner>;
 
     fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::Environment<Interner> {
-        let mut clauses = Vec::new();
-        for pred in &self.predicates {
-            if pred.is_error() {
-                // for env, we just ignore errors
-                continue;
-            }
-            let program_clause: chalk_ir::ProgramClause<Interner> =
-                pred.clone().to_chalk(db).cast(&Interner);
-            clauses.push(program_clause.into_from_env_clause(&Interner));
-        }
-        chalk_ir::Environment::new(&Interner).add_clauses(&Interner, clauses)
+        to_chalk_span!("TraitEnvironment");
+        environment_to_chalk(db, &self, None)
     }
 
     fn from_chalk(
-        _db: &dyn HirDatabase,
-        _env: chalk_ir::Environment<Interner>,
+        db: &dyn HirDatabase,
+        env: chalk_ir::Environment<Interner>,
     ) -> Arc<TraitEnvironment> {
-        unimplemented!()
+        let predicates = env
+            .clauses
+            .iter(&Interner)
+            .map(|clause| from_chalk::<FromEnvClause, _>(db, Some(clause.clone())).0)
+            .filter(|pred| !pred.is_error())
+            .collect();
+        Arc::new(TraitEnvironment { predicates })
+    }
+}
+
+/// Converts the assumptions of `env`, leaving out (and recording in `skipped`)
+/// the ones that errored.
+fn environment_to_chalk(
+    db: &dyn HirDatabase,
+    env: &TraitEnvironment,
+    mut skipped: Option<&mut Vec<SkippedPredicate>>,
+) -> chalk_ir::Environment<Interner> {
+    let mut clauses = Vec::new();
+    for pred in &env.predicates {
+        if skip_if_error(pred, SkipReason::ErrorInEnvironment, &mut skipped) {
+            continue;
+        }
+        clauses.extend(FromEnvClause(pred.clone()).to_chalk(db));
     }
+    chalk_ir::Environment::new(&Interner).add_clauses(&Interner, clauses)
 }
 
 impl<T: ToChalk> ToChalk for InEnvironment<T>
@@ -597,33 +1125,67 @@ where
     }
 }
 
+/// Checks that `t` doesn't contain any inference variables, i.e. that it has
+/// been canonicalized and can be passed to `to_chalk`. This only does something
+/// in debug builds; calling it where a goal is constructed gives a much more
+/// useful panic location than the one deep inside the conversion.
+#[track_caller]
+pub(crate) fn assert_canonicalized<T: TypeWalk + fmt::Debug>(t: &T) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let mut found = None;
+    t.walk(&mut |ty| {
+        if let Ty::InferenceVar(var, _) = ty {
+            found.get_or_insert(*var);
+        }
+    });
+    if let Some(var) = found {
+        panic!("uncanonicalized infer ty {:?} in {:?}", var, t);
+    }
+}
+
 pub(super) fn make_binders<T>(value: T, num_vars: usize) -> chalk_ir::Binders<T>
 where
     T: HasInterner<Interner = Interner>,
 {
-    chalk_ir::Binders::new(
+    make_binders_from_kinds(
+        value,
         chalk_ir::VariableKinds::from_iter(
             &Interner,
             std::iter::repeat(chalk_ir::VariableKind::Ty(chalk_ir::TyVariableKind::General))
                 .take(num_vars),
         ),
-        value,
     )
 }
 
-pub(super) fn convert_where_clauses(
+/// Like `make_binders`, but for callers that already have the binders' kinds
+/// at hand and don't need them rebuilt.
+pub(super) fn make_binders_from_kinds<T>(
+    value: T,
+    kinds: chalk_ir::VariableKinds<Interner>,
+) -> chalk_ir::Binders<T>
+where
+    T: HasInterner<Interner = Interner>,
+{
+    chalk_ir::Binders::new(kinds, value)
+}
+
+/// Converts the where clauses of `def`, leaving out (and recording in
+/// `skipped`) the ones that errored.
+pub(crate) fn convert_where_clauses(
     db: &dyn HirDatabase,
     def: GenericDefId,
     substs: &Substs,
+    mut skipped: Option<&mut Vec<SkippedPredicate>>,
 ) -> Vec<chalk_ir::QuantifiedWhereClause<Interner>> {
     let generic_predicates = db.generic_predicates(def);
     let mut result = Vec::with_capacity(generic_predicates.len());
     for pred in generic_predicates.iter() {
-        if pred.value.is_error() {
-            // skip errored predicates completely
+        if skip_if_error(&pred.value, SkipReason::ErrorInWhereClause, &mut skipped) {
             continue;
         }
-        result.push(pred.clone().subst(substs).to_chalk(db));
+        result.extend(pred.clone().subst(substs).to_chalk(db));
     }
     result
 }
@@ -637,40 +1199,57 @@ pub(super) fn generic_predicate_to_inline_bound(
     // We don't have a special type for this, but Chalk does.
     match pred {
         GenericPredicate::Implemented(trait_ref) => {
-            if &trait_ref.substs[0] != self_ty {
-                // we can only convert predicates back to type bounds if they
-                // have the expected self type
-                return None;
-            }
-            let args_no_self = trait_ref.substs[1..]
-                .iter()
-                .map(|ty| ty.clone().to_chalk(db).cast(&Interner))
-                .collect();
-            let trait_bound =
-                rust_ir::TraitBound { trait_id: trait_ref.trait_.to_chalk(db), args_no_self };
+            let trait_bound = trait_bound_without_self(db, trait_ref, self_ty)?;
             Some(rust_ir::InlineBound::TraitBound(trait_bound))
         }
         GenericPredicate::Projection(proj) => {
-            if &proj.projection_ty.parameters[0] != self_ty {
-                return None;
-            }
-            let trait_ = match proj.projection_ty.associated_ty.lookup(db.upcast()).container {
-                AssocContainerId::TraitId(t) => t,
-                _ => panic!("associated type not in trait"),
-            };
-            let args_no_self = proj.projection_ty.parameters[1..]
-                .iter()
-                .map(|ty| ty.clone().to_chalk(db).cast(&Interner))
-                .collect();
+            let trait_ = assoc_ty_trait(db.upcast(), proj.projection_ty.associated_ty)?;
+            let trait_ref = TraitRef { trait_, substs: proj.projection_ty.parameters.clone() };
             let alias_eq_bound = rust_ir::AliasEqBound {
                 value: proj.ty.clone().to_chalk(db),
-                trait_bound: rust_ir::TraitBound { trait_id: trait_.to_chalk(db), args_no_self },
+                trait_bound: trait_bound_without_self(db, &trait_ref, self_ty)?,
                 associated_ty_id: TypeAliasAsAssocType(proj.projection_ty.associated_ty)
                     .to_chalk(db),
                 parameters: Vec::new(), // FIXME we don't support generic associated types yet
             };
             Some(rust_ir::InlineBound::AliasEqBound(alias_eq_bound))
         }
-        GenericPredicate::Error => None,
+        GenericPredicate::Opaque(_) | GenericPredicate::Error => None,
+    }
+}
+
+/// Lowers the bounds on an associated type declared in a trait, like `Sized` in
+/// `type Item: Sized;`. Bounds we can't convert are left out.
+pub(super) fn associated_ty_bounds(
+    db: &dyn HirDatabase,
+    type_alias: TypeAliasId,
+) -> Vec<rust_ir::QuantifiedInlineBound<Interner>> {
+    // we could/should maybe move lowering the bounds to a separate query in `lower`
+    let type_alias_data = db.type_alias_data(type_alias);
+    let resolver = hir_def::resolver::HasResolver::resolver(type_alias, db.upcast());
+    let ctx = crate::TyLoweringContext::new(db, &resolver)
+        .with_type_param_mode(crate::lower::TypeParamLoweringMode::Variable);
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    type_alias_data
+        .bounds
+        .iter()
+        .flat_map(|bound| GenericPredicate::from_type_bound(&ctx, bound, self_ty.clone()))
+        .filter_map(|pred| generic_predicate_to_inline_bound(db, &pred, &self_ty))
+        .map(|bound| make_binders(bound.shifted_in(&Interner), 0))
+        .collect()
+}
+
+/// Converts `trait_ref` to a trait bound on `self_ty`, which leaves out the
+/// `Self` type. We can only do that if it has the expected `Self` type.
+fn trait_bound_without_self(
+    db: &dyn HirDatabase,
+    trait_ref: &TraitRef,
+    self_ty: &Ty,
+) -> Option<rust_ir::TraitBound<Interner>> {
+    let (trait_self_ty, params) = trait_ref.substs.split_self()?;
+    if trait_self_ty != self_ty {
+        return None;
     }
+    let args_no_self = params.iter().map(|ty| ty.clone().to_chalk(db).cast(&Interner)).collect();
+    Some(rust_ir::TraitBound { trait_id: trait_ref.trait_.to_chalk(db), args_no_self })
 }
@@ -0,0 +1,1360 @@
+use base_db::{fixture::WithFixture, FileId};
+use hir_def::db::DefDatabase;
+use test_utils::{bench, skip_slow_tests};
+
+use crate::{
+    display::HirDisplay, infer::unify::InferenceTable, test_db::TestDB, GenericArg,
+    GenericPredicate, Mutability, Safety, Scalar, Substs, TraitEnvironment, TraitRef, Ty,
+    TyVariableKind,
+};
+
+use super::*;
+
+mod fixtures;
+mod ty_kinds;
+
+#[test]
+#[should_panic(expected = "canonicalized")]
+fn inference_var_to_chalk_panics() {
+    let db = TestDB::default();
+    InferenceTable::new().new_type_var().to_chalk(&db);
+}
+
+#[test]
+fn canonicalized_ty_to_chalk_does_not_panic() {
+    let db = TestDB::default();
+    let bound = Ty::BoundVar(crate::BoundVar::new(crate::DebruijnIndex::INNERMOST, 0));
+    let ty = Ty::Tuple(2, Substs(vec![bound, Ty::Scalar(Scalar::Bool)].into()));
+    assert_canonicalized(&ty);
+    let chalk_ty = ty.clone().to_chalk(&db);
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), ty);
+}
+
+// `assert_canonicalized` only checks in debug builds
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "uncanonicalized infer ty")]
+fn assert_canonicalized_finds_nested_inference_var() {
+    let var = InferenceTable::new().new_integer_var();
+    assert_canonicalized(&Ty::Slice(Substs::single(var)));
+}
+
+#[test]
+fn pointer_sized_scalars_round_trip() {
+    use crate::primitive::{IntTy, UintTy};
+
+    let db = TestDB::default();
+    let scalars = [
+        Scalar::Uint(UintTy::Usize),
+        Scalar::Uint(UintTy::U64),
+        Scalar::Int(IntTy::Isize),
+        Scalar::Int(IntTy::I64),
+    ];
+    for &scalar in scalars.iter() {
+        let chalk_ty = Ty::Scalar(scalar).to_chalk(&db);
+        assert_eq!(chalk_ty.kind(&Interner), &chalk_ir::TyKind::Scalar(scalar.to_chalk(&db)));
+        assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), Ty::Scalar(scalar));
+    }
+    assert_ne!(
+        Ty::Scalar(Scalar::Uint(UintTy::Usize)).to_chalk(&db),
+        Ty::Scalar(Scalar::Uint(UintTy::U64)).to_chalk(&db)
+    );
+    assert_ne!(
+        Ty::Scalar(Scalar::Int(IntTy::Isize)).to_chalk(&db),
+        Ty::Scalar(Scalar::Int(IntTy::I64)).to_chalk(&db)
+    );
+}
+
+#[test]
+fn every_scalar_round_trips() {
+    use crate::primitive::{FloatTy, IntTy, UintTy};
+
+    let db = TestDB::default();
+    let ints = [IntTy::Isize, IntTy::I8, IntTy::I16, IntTy::I32, IntTy::I64, IntTy::I128];
+    let uints = [UintTy::Usize, UintTy::U8, UintTy::U16, UintTy::U32, UintTy::U64, UintTy::U128];
+    let scalars =
+        [Scalar::Bool, Scalar::Char, Scalar::Float(FloatTy::F32), Scalar::Float(FloatTy::F64)]
+            .iter()
+            .copied()
+            .chain(ints.iter().map(|&it| Scalar::Int(it)))
+            .chain(uints.iter().map(|&it| Scalar::Uint(it)))
+            .collect::<Vec<_>>();
+    let mut chalk_scalars = Vec::new();
+    for &scalar in &scalars {
+        let chalk_scalar = scalar.to_chalk(&db);
+        assert_eq!(from_chalk::<Scalar, _>(&db, chalk_scalar), scalar);
+        assert!(!chalk_scalars.contains(&chalk_scalar), "{:?} is not unique", chalk_scalar);
+        chalk_scalars.push(chalk_scalar);
+    }
+    assert_eq!(chalk_scalars.len(), 16);
+}
+
+#[test]
+fn array_length_is_usize() {
+    let db = TestDB::default();
+    let array = Ty::Array(Substs::single(Ty::Scalar(Scalar::Bool)));
+    let chalk_ty = array.clone().to_chalk(&db);
+    match chalk_ty.kind(&Interner) {
+        chalk_ir::TyKind::Array(_, len) => {
+            let len_ty = &len.data(&Interner).ty;
+            assert_eq!(
+                len_ty.kind(&Interner),
+                &chalk_ir::TyKind::Scalar(chalk_ir::Scalar::Uint(chalk_ir::UintTy::Usize))
+            );
+        }
+        kind => panic!("expected an array, got {:?}", kind),
+    }
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), array);
+}
+
+#[test]
+fn array_with_inference_var_length_from_chalk() {
+    use crate::primitive::UintTy;
+
+    let db = TestDB::default();
+    let usize_ty = Ty::Scalar(Scalar::Uint(UintTy::Usize)).to_chalk(&db);
+    let len = chalk_ir::ConstData {
+        ty: usize_ty,
+        value: chalk_ir::ConstValue::InferenceVar(chalk_ir::InferenceVar::from(0)),
+    }
+    .intern(&Interner);
+    let elem = Ty::Scalar(Scalar::Bool);
+    let chalk_ty = chalk_ir::TyKind::Array(elem.clone().to_chalk(&db), len).intern(&Interner);
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), Ty::Array(Substs::single(elem)));
+}
+
+/// Infers the body of the first function in `ra_fixture` and returns the
+/// first closure type found in it.
+fn infer_first_closure(ra_fixture: &str) -> (TestDB, Ty) {
+    let (db, file_id) = TestDB::with_single_file(ra_fixture);
+    let func = db.first_fn(file_id);
+    let infer = db.infer(func.into());
+    let closure = infer
+        .type_of_expr
+        .iter()
+        .map(|(_, ty)| ty)
+        .find(|ty| ty.as_closure().is_some())
+        .expect("no closure in fixture")
+        .clone();
+    (db, closure)
+}
+
+#[test]
+fn closure_ty_round_trip() {
+    let (db, ty) = infer_first_closure(
+        r#"
+fn main() {
+    let f = |x: u32| x;
+}
+"#,
+    );
+    let (def, expr, substs) = match ty.clone() {
+        Ty::Closure(def, expr, substs) => (def, expr, substs),
+        _ => unreachable!(),
+    };
+    assert_eq!(closure_ty(&db, def, expr, substs), ty);
+    assert_eq!(lookup_closure_ty(&db, &ty.clone().to_chalk(&db)), Some(ty));
+    assert_eq!(lookup_closure_ty(&db, &Ty::Str.to_chalk(&db)), None);
+}
+
+#[test]
+fn error_predicate_to_chalk_is_skipped() {
+    let db = TestDB::default();
+    assert_eq!(GenericPredicate::Error.to_chalk(&db), None);
+    assert_eq!(from_chalk::<GenericPredicate, _>(&db, None), GenericPredicate::Error);
+
+    let env = Arc::new(TraitEnvironment { predicates: vec![GenericPredicate::Error] });
+    let chalk_env = env.to_chalk(&db);
+    assert!(chalk_env.clauses.is_empty(&Interner));
+}
+
+#[test]
+fn skipped_error_predicates_are_recorded() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let pred = implemented_by_bound_self(&db, file_id);
+    let predicates = vec![pred, GenericPredicate::Error];
+    let skipped_error = |reason| SkippedPredicate { predicate: GenericPredicate::Error, reason };
+
+    let mut skipped = Vec::new();
+    let env = TraitEnvironment { predicates: predicates.clone() };
+    let chalk_env = environment_to_chalk(&db, &env, Some(&mut skipped));
+    assert_eq!(chalk_env.clauses.len(&Interner), 1);
+    assert_eq!(skipped, [skipped_error(SkipReason::ErrorInEnvironment)]);
+
+    skipped.clear();
+    let chalk_ty = dyn_to_chalk(&db, &predicates, Some(&mut skipped));
+    assert!(matches!(chalk_ty.kind(&Interner), chalk_ir::TyKind::Dyn(_)));
+    assert_eq!(skipped, [skipped_error(SkipReason::ErrorInDynBound)]);
+}
+
+#[test]
+fn error_trait_ref_to_chalk_is_skipped() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let trait_ = db.first_trait(file_id);
+    let trait_ref = |substs| TraitRef { trait_, substs };
+
+    let errors = [
+        trait_ref(Substs::single(Ty::Error)),
+        trait_ref(Substs::single(Ty::Unknown)),
+        trait_ref(Substs::empty()),
+    ];
+    for error in errors.iter() {
+        assert!(error.is_error());
+        assert_eq!(GenericPredicate::Implemented(error.clone()).to_chalk(&db), None);
+    }
+    let str_ref = trait_ref(Substs::single(Ty::Str));
+    assert!(!str_ref.is_error());
+    assert!(GenericPredicate::Implemented(str_ref).to_chalk(&db).is_some());
+}
+
+#[test]
+fn from_env_clause_round_trip() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let pred = implemented_by_bound_self(&db, file_id);
+
+    let clause = FromEnvClause(pred.clone()).to_chalk(&db).expect("not an error predicate");
+    let consequence = &clause.data(&Interner).0.skip_binders().consequence;
+    assert!(matches!(consequence, chalk_ir::DomainGoal::FromEnv(chalk_ir::FromEnv::Trait(_))));
+    assert_eq!(from_chalk::<FromEnvClause, _>(&db, Some(clause)).0, pred);
+
+    let env = Arc::new(TraitEnvironment { predicates: vec![pred, GenericPredicate::Error] });
+    let back: Arc<TraitEnvironment> = from_chalk(&db, env.clone().to_chalk(&db));
+    assert_eq!(back.predicates, env.predicates[..1]);
+}
+
+#[test]
+fn trait_ref_accessors() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo<T, U> {}");
+    let trait_ = db.first_trait(file_id);
+    let substs = Substs(vec![Ty::Str, Ty::Never, Ty::Scalar(Scalar::Bool)].into());
+    let trait_ref = TraitRef { trait_, substs };
+    assert_eq!(trait_ref.self_ty(), &Ty::Str);
+    assert_eq!(trait_ref.type_params(), &[Ty::Never, Ty::Scalar(Scalar::Bool)][..]);
+
+    let trait_ref = TraitRef { trait_, substs: Substs::single(Ty::Str) };
+    assert_eq!(trait_ref.self_ty(), &Ty::Str);
+    assert!(trait_ref.type_params().is_empty());
+}
+
+#[test]
+fn make_binders_from_kinds_agrees_with_make_binders() {
+    let value = Ty::Scalar(Scalar::Bool).to_chalk(&TestDB::default());
+    let kinds = chalk_ir::VariableKinds::from_iter(
+        &Interner,
+        vec![chalk_ir::VariableKind::Ty(chalk_ir::TyVariableKind::General); 3],
+    );
+    assert_eq!(make_binders_from_kinds(value.clone(), kinds), make_binders(value, 3));
+}
+
+#[test]
+fn error_and_unknown_stay_distinct_in_substitutions() {
+    let db = TestDB::default();
+    // a fn pointer needs at least the return type, so this one converts to `Ty::Error`
+    let fn_ptr = chalk_ir::TyKind::Function(chalk_ir::FnPointer {
+        num_binders: 0,
+        sig: chalk_ir::FnSig { abi: (), safety: chalk_ir::Safety::Safe, variadic: false },
+        substitution: chalk_ir::FnSubst(Substs::empty().to_chalk(&db)),
+    })
+    .intern(&Interner);
+    let error = chalk_ir::TyKind::Error.intern(&Interner);
+    let var = chalk_ir::TyKind::InferenceVar(
+        chalk_ir::InferenceVar::from(0),
+        chalk_ir::TyVariableKind::General,
+    )
+    .intern(&Interner);
+    let substitution = chalk_ir::Substitution::from_iter(&Interner, vec![var, error, fn_ptr]);
+    let tuple = chalk_ir::TyKind::Tuple(3, substitution).intern(&Interner);
+
+    let ty: Ty = from_chalk(&db, tuple);
+    assert_eq!(ty, Ty::Tuple(3, Substs(vec![Ty::Unknown, Ty::Error, Ty::Error].into())));
+    assert!(ty.contains_unknown());
+
+    // Chalk has a single error type, which both map to
+    assert_eq!(Ty::Error.to_chalk(&db), Ty::Unknown.to_chalk(&db));
+}
+
+#[test]
+fn empty_substs_to_chalk_is_the_empty_substitution() {
+    let db = TestDB::default();
+    assert_eq!(Substs::empty().to_chalk(&db), chalk_ir::Substitution::empty(&Interner));
+    assert_eq!(
+        from_chalk::<Substs, _>(&db, chalk_ir::Substitution::empty(&Interner)),
+        Substs::empty()
+    );
+}
+
+#[test]
+fn generic_arg_ty_round_trip() {
+    let db = TestDB::default();
+    let arg = GenericArg::Ty(Ty::Scalar(Scalar::Char));
+    let chalk_arg = arg.clone().to_chalk(&db);
+    assert_eq!(chalk_arg.ty(&Interner), Some(&Ty::Scalar(Scalar::Char).to_chalk(&db)));
+    assert_eq!(from_chalk::<GenericArg, _>(&db, chalk_arg), arg);
+}
+
+#[test]
+fn generic_arg_lifetime_from_chalk_is_unknown() {
+    let db = TestDB::default();
+    let lifetime = LifetimeData::Static.intern(&Interner);
+    let arg = from_chalk::<GenericArg, _>(&db, lifetime.cast(&Interner));
+    assert_eq!(arg, GenericArg::Ty(Ty::Unknown));
+}
+
+#[test]
+fn generic_arg_const_from_chalk_is_unknown() {
+    let db = TestDB::default();
+    let const_ = chalk_ir::ConstData {
+        ty: Ty::Scalar(Scalar::Uint(UintTy::Usize)).to_chalk(&db),
+        value: chalk_ir::ConstValue::Concrete(chalk_ir::ConcreteConst { interned: () }),
+    }
+    .intern(&Interner);
+    let arg = from_chalk::<GenericArg, _>(&db, const_.cast(&Interner));
+    assert_eq!(arg, GenericArg::Ty(Ty::Unknown));
+}
+
+fn implemented_by_bound_self(db: &TestDB, file_id: FileId) -> GenericPredicate {
+    let trait_ = db.first_trait(file_id);
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    GenericPredicate::Implemented(TraitRef { trait_, substs: Substs::single(self_ty) })
+}
+
+#[test]
+fn canonical_obligation_in_environment_round_trips() {
+    let (db, file_id) = TestDB::with_single_file("trait Iterator { type Item; }");
+    let trait_ = db.first_trait(file_id);
+    let item = db.trait_data(trait_).associated_types().next().unwrap();
+    let var = |idx| Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, idx));
+    let bool_ = Ty::Scalar(Scalar::Bool);
+    let env = Arc::new(TraitEnvironment {
+        predicates: vec![GenericPredicate::Implemented(TraitRef {
+            trait_,
+            substs: Substs::single(bool_),
+        })],
+    });
+    let obligations = vec![
+        Obligation::Trait(TraitRef { trait_, substs: Substs::single(var(0)) }),
+        Obligation::Projection(ProjectionPredicate {
+            projection_ty: ProjectionTy { associated_ty: item, parameters: Substs::single(var(0)) },
+            ty: var(1),
+        }),
+    ];
+    for obligation in obligations {
+        let goal = Canonical {
+            kinds: vec![
+                VariableKind::Ty(TyVariableKind::General),
+                VariableKind::Ty(TyVariableKind::Integer),
+            ]
+            .into(),
+            value: InEnvironment::new(env.clone(), obligation.clone()),
+        };
+        let chalk_goal = goal.clone().to_chalk(&db);
+        assert_eq!(chalk_goal.binders.len(&Interner), 2);
+        assert_eq!(chalk_goal.value.environment, env.clone().to_chalk(&db));
+        assert_eq!(chalk_goal.value.goal, obligation.to_chalk(&db));
+
+        let back: Canonical<InEnvironment<Obligation>> = from_chalk(&db, chalk_goal);
+        assert_eq!(back, goal);
+    }
+}
+
+#[test]
+fn canonical_const_var_round_trips() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let trait_ = db.first_trait(file_id);
+    let usize_ = Ty::Scalar(Scalar::Uint(crate::primitive::UintTy::Usize));
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let obligation = Obligation::Trait(TraitRef { trait_, substs: Substs::single(self_ty) });
+    // `[T; N]: Foo`, where `N` is the const var, but array lengths aren't in
+    // `Ty::Array` yet
+    let goal = Canonical::new(
+        InEnvironment::new(Arc::new(TraitEnvironment { predicates: Vec::new() }), obligation),
+        vec![VariableKind::Ty(TyVariableKind::General), VariableKind::Const(usize_.clone())],
+    );
+
+    let chalk_goal = goal.clone().to_chalk(&db);
+    let kinds = chalk_goal.binders.iter(&Interner).map(|it| it.kind.clone()).collect::<Vec<_>>();
+    assert_eq!(kinds[1], chalk_ir::VariableKind::Const(usize_.to_chalk(&db)));
+
+    let back: Canonical<InEnvironment<Obligation>> = from_chalk(&db, chalk_goal);
+    assert_eq!(back, goal);
+}
+
+#[test]
+fn well_formed_obligation_round_trips() {
+    let (db, file_id) = TestDB::with_single_file("struct S<T>(T);");
+    let adt = db.first_adt(file_id);
+    let ty = Ty::Adt(adt, Substs::single(Ty::Scalar(Scalar::Uint(crate::primitive::UintTy::U32))));
+    let obligation = Obligation::WellFormed(ty.clone());
+
+    let goal = obligation.clone().to_chalk(&db);
+    assert_eq!(goal, chalk_ir::DomainGoal::WellFormed(chalk_ir::WellFormed::Ty(ty.to_chalk(&db))));
+    assert_eq!(from_chalk::<Obligation, _>(&db, goal), obligation);
+}
+
+#[test]
+fn predicate_in_nested_binder_is_shifted_by_depth() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let pred = implemented_by_bound_self(&db, file_id);
+
+    let where_clause = generic_predicate_to_chalk_at(&db, pred.clone(), DebruijnIndex::ONE)
+        .expect("not an error predicate");
+    let self_ty = match where_clause.skip_binders() {
+        chalk_ir::WhereClause::Implemented(trait_ref) => trait_ref.self_type_parameter(&Interner),
+        _ => panic!("expected an `Implemented` where clause"),
+    };
+    // One level for the where clause itself, one for the binder it's nested in.
+    assert_eq!(self_ty.bound_var(&Interner).map(|bv| bv.debruijn.depth()), Some(2));
+
+    let back = generic_predicate_from_chalk_at(&db, Some(where_clause), DebruijnIndex::ONE);
+    assert_eq!(back, pred);
+}
+
+#[test]
+fn nested_predicate_read_at_wrong_depth_is_error() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let pred = implemented_by_bound_self(&db, file_id);
+    let where_clause = generic_predicate_to_chalk_at(&db, pred, DebruijnIndex::INNERMOST);
+    let back = generic_predicate_from_chalk_at(&db, where_clause, DebruijnIndex::ONE);
+    assert_eq!(back, GenericPredicate::Error);
+}
+
+#[test]
+fn where_clause_using_its_binders_from_chalk_is_error() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let trait_ref = match implemented_by_bound_self(&db, file_id) {
+        GenericPredicate::Implemented(trait_ref) => trait_ref,
+        _ => unreachable!(),
+    };
+    // `for<T> T: Foo`, the bound `Self` refers to the where clause's own binder
+    let kinds = chalk_ir::VariableKinds::from1(
+        &Interner,
+        chalk_ir::VariableKind::Ty(chalk_ir::TyVariableKind::General),
+    );
+    let where_clause =
+        chalk_ir::Binders::new(kinds, chalk_ir::WhereClause::Implemented(trait_ref.to_chalk(&db)));
+
+    let back = generic_predicate_from_chalk_at(&db, Some(where_clause), DebruijnIndex::INNERMOST);
+    assert_eq!(back, GenericPredicate::Error);
+}
+
+#[test]
+fn where_clauses_from_chalk_drops_unrepresentable_clauses() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo {}");
+    let pred = implemented_by_bound_self(&db, file_id);
+    let implemented = generic_predicate_to_chalk_at(&db, pred.clone(), DebruijnIndex::INNERMOST)
+        .expect("not an error predicate");
+    let outlives = make_binders(
+        chalk_ir::WhereClause::TypeOutlives(chalk_ir::TypeOutlives {
+            ty: Ty::Scalar(Scalar::Bool).to_chalk(&db),
+            lifetime: chalk_ir::LifetimeData::Static.intern(&Interner),
+        }),
+        0,
+    );
+
+    let preds = where_clauses_from_chalk(&db, &[outlives, implemented]);
+    assert_eq!(preds, vec![pred]);
+}
+
+#[test]
+fn ty_accessors() {
+    let (db, closure) = infer_first_closure(
+        r#"
+struct S;
+trait Foo {}
+fn main() {
+    let f = || ();
+}
+"#,
+    );
+    let (_, _, substs) = closure.as_closure().unwrap();
+    let fn_ptr = substs[0].as_fn_ptr().expect("closure signature is a fn pointer");
+    assert_eq!(fn_ptr.num_args, 0);
+    assert_eq!(fn_ptr.substs.as_single(), &Ty::unit());
+    assert_eq!(Ty::Str.as_closure(), None);
+    assert_eq!(Ty::Str.as_fn_ptr(), None);
+
+    let file_id = FileId(0);
+    let adt = db.first_adt(file_id);
+    let adt_ty = Ty::Adt(adt, Substs::empty());
+    assert_eq!(adt_ty.as_adt(), Some((adt, &Substs::empty())));
+    assert_eq!(Ty::Str.as_adt(), None);
+
+    let trait_ = db.first_trait(file_id);
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let pred = GenericPredicate::Implemented(TraitRef { trait_, substs: Substs::single(self_ty) });
+    let dyn_ty = Ty::Dyn(vec![pred.clone()].into());
+    assert_eq!(dyn_ty.as_dyn_predicates(), Some(&[pred][..]));
+    assert_eq!(dyn_ty.dyn_trait(), Some(trait_));
+    assert_eq!(adt_ty.as_dyn_predicates(), None);
+}
+
+#[test]
+fn never_and_str_round_trip() {
+    let db = TestDB::default();
+    for ty in &[Ty::Never, Ty::Str] {
+        let chalk_ty = ty.clone().to_chalk(&db);
+        assert_eq!(&from_chalk::<Ty, _>(&db, chalk_ty), ty);
+    }
+}
+
+#[test]
+fn str_and_u8_slice_stay_distinct() {
+    let db = TestDB::default();
+    let u8_slice = Ty::Slice(Substs::single(Ty::Scalar(Scalar::Uint(UintTy::U8))));
+    let str_ref = Ty::Ref(Mutability::Not, Substs::single(Ty::Str));
+    let slice_ref = Ty::Ref(Mutability::Not, Substs::single(u8_slice.clone()));
+
+    for (a, b) in [(Ty::Str, u8_slice), (str_ref, slice_ref)].iter().cloned() {
+        let (chalk_a, chalk_b) = (a.clone().to_chalk(&db), b.clone().to_chalk(&db));
+        assert_ne!(chalk_a, chalk_b);
+        assert_eq!(from_chalk::<Ty, _>(&db, chalk_a), a);
+        assert_eq!(from_chalk::<Ty, _>(&db, chalk_b), b);
+
+        let tys = crate::Canonical { value: (a, b), kinds: Arc::new([]) };
+        assert_eq!(crate::infer::unify(&tys), None);
+    }
+}
+
+#[test]
+fn ref_with_lifetime_var_from_chalk() {
+    let db = TestDB::default();
+    let lifetime =
+        chalk_ir::LifetimeData::InferenceVar(chalk_ir::InferenceVar::from(0)).intern(&Interner);
+    let chalk_ty =
+        chalk_ir::TyKind::Ref(chalk_ir::Mutability::Mut, lifetime, Ty::Str.to_chalk(&db))
+            .intern(&Interner);
+    assert_eq!(
+        from_chalk::<Ty, _>(&db, chalk_ty),
+        Ty::Ref(Mutability::Mut, Substs::single(Ty::Str))
+    );
+}
+
+#[test]
+fn raw_pointer_mutability_round_trips() {
+    use crate::primitive::IntTy;
+
+    let db = TestDB::default();
+    let i32_ty = Ty::Scalar(Scalar::Int(IntTy::I32));
+    let const_ptr = Ty::Raw(Mutability::Not, Substs::single(i32_ty.clone()));
+    let mut_ptr = Ty::Raw(Mutability::Mut, Substs::single(i32_ty));
+
+    let (chalk_const, chalk_mut) = (const_ptr.clone().to_chalk(&db), mut_ptr.clone().to_chalk(&db));
+    assert_ne!(chalk_const, chalk_mut);
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_const), const_ptr);
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_mut), mut_ptr);
+
+    let tys = crate::Canonical { value: (const_ptr, mut_ptr), kinds: Arc::new([]) };
+    assert_eq!(crate::infer::unify(&tys), None);
+}
+
+#[test]
+fn display_via_chalk_renders_nested_generics() {
+    let (db, ty) = infer_first_closure(
+        r#"
+struct Vec<T>(T);
+enum Option<T> { Some(T), None }
+fn main() {
+    let f = |x: Vec<Option<i32>>| x;
+}
+"#,
+    );
+    let (_, _, substs) = ty.as_closure().unwrap();
+    let vec_ty = substs[0].as_fn_ptr().unwrap().substs[0].clone();
+    assert_eq!(vec_ty.display_via_chalk(&db), "Vec<[?0 := Option<[?0 := Int(I32)]>]>");
+}
+
+#[test]
+fn fn_ptr_variadic_round_trip() {
+    let db = TestDB::default();
+    let u8_ty = Ty::Scalar(Scalar::Uint(UintTy::U8));
+    for &variadic in &[true, false] {
+        let ty = Ty::Function(crate::FnPointer {
+            num_args: 1,
+            sig: crate::FnSig { variadic, safety: crate::Safety::Safe },
+            substs: Substs(vec![u8_ty.clone(), Ty::unit()].into()),
+        });
+        let back = from_chalk::<Ty, _>(&db, ty.clone().to_chalk(&db));
+        let fn_ptr = back.as_fn_ptr().unwrap();
+        assert_eq!(fn_ptr.sig.variadic, variadic);
+        assert_eq!(fn_ptr.num_args, 1);
+        assert_eq!(back, ty);
+    }
+}
+
+#[test]
+fn fn_ptr_without_return_type_from_chalk_is_error() {
+    let db = TestDB::default();
+    let ty = chalk_ir::TyKind::Function(chalk_ir::FnPointer {
+        num_binders: 0,
+        sig: chalk_ir::FnSig { abi: (), safety: chalk_ir::Safety::Safe, variadic: false },
+        substitution: chalk_ir::FnSubst(Substs::empty().to_chalk(&db)),
+    })
+    .intern(&Interner);
+    assert_eq!(from_chalk::<Ty, _>(&db, ty), Ty::Error);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "followed by the return type")]
+fn fn_ptr_with_mismatched_num_args_to_chalk_panics() {
+    let db = TestDB::default();
+    let ty = Ty::Function(crate::FnPointer {
+        num_args: 1,
+        sig: crate::FnSig { variadic: false, safety: crate::Safety::Safe },
+        substs: Substs::single(Ty::unit()),
+    });
+    ty.to_chalk(&db);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "fn pointer substitution is mis-shifted")]
+fn unshifted_fn_ptr_substitution_is_caught() {
+    let db = TestDB::default();
+    let bound = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let substitution = Substs::single(bound).to_chalk(&db);
+    debug_assert_shifted_in(&substitution, DebruijnIndex::ONE, "fn pointer substitution");
+}
+
+#[test]
+fn type_alias_ids_round_trip() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Tr { type Output; }
+struct S;
+impl Tr for S { type Output = u8; }
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let def_map = module.def_map(&db);
+    let impl_id = def_map[module.local_id].scope.impls().next().unwrap();
+    let value_alias = match db.impl_data(impl_id).items[..] {
+        [hir_def::AssocItemId::TypeAliasId(it)] => it,
+        _ => panic!("expected a single associated type in the impl"),
+    };
+    let trait_ = db.first_trait(file_id);
+    let assoc_alias = db.trait_data(trait_).associated_type_by_name(&name![Output]).unwrap();
+
+    let id = TypeAliasAsAssocType(assoc_alias).to_chalk(&db);
+    assert_eq!(from_chalk::<TypeAliasAsAssocType, _>(&db, id).0, assoc_alias);
+    let id = TypeAliasAsForeignType(assoc_alias).to_chalk(&db);
+    assert_eq!(from_chalk::<TypeAliasAsForeignType, _>(&db, id).0, assoc_alias);
+
+    let value_id = TypeAliasAsValue(value_alias).to_chalk(&db);
+    assert_eq!(from_chalk::<TypeAliasAsValue, _>(&db, value_id).0, value_alias);
+    let value = db.associated_ty_value(module.krate(), value_id);
+    assert_eq!(value.impl_id, hir_def::ImplId::to_chalk(impl_id, &db));
+    assert_eq!(value.associated_ty_id, TypeAliasAsAssocType(assoc_alias).to_chalk(&db));
+}
+
+#[test]
+fn trait_id_from_chalk_needs_no_db() {
+    let chalk_id = chalk_ir::TraitId(base_db::salsa::InternId::from(7u32));
+    let trait_id = trait_id_from_chalk(chalk_id);
+    assert_eq!(trait_id.as_intern_id(), chalk_id.0);
+}
+
+#[test]
+fn safety_round_trip() {
+    let db = TestDB::default();
+    for &safety in &[Safety::Safe, Safety::Unsafe] {
+        assert_eq!(from_chalk::<Safety, _>(&db, safety.to_chalk(&db)), safety);
+
+        let ty = Ty::Function(crate::FnPointer {
+            num_args: 0,
+            sig: crate::FnSig { variadic: false, safety },
+            substs: Substs::single(Ty::unit()),
+        });
+        assert_eq!(from_chalk::<Ty, _>(&db, ty.clone().to_chalk(&db)), ty);
+    }
+    assert_eq!(Safety::Unsafe.to_chalk(&db), chalk_ir::Safety::Unsafe);
+}
+
+#[test]
+fn mutability_round_trip() {
+    let db = TestDB::default();
+    for &mutability in &[Mutability::Not, Mutability::Mut] {
+        assert_eq!(from_chalk::<Mutability, _>(&db, mutability.to_chalk(&db)), mutability);
+
+        let tys = [
+            Ty::Ref(mutability, Substs::single(Ty::Str)),
+            Ty::Raw(mutability, Substs::single(Ty::Str)),
+        ];
+        for ty in tys.iter() {
+            assert_eq!(&from_chalk::<Ty, _>(&db, ty.clone().to_chalk(&db)), ty);
+        }
+    }
+    assert_eq!(Mutability::Not.to_chalk(&db), chalk_ir::Mutability::Not);
+    assert_eq!(Mutability::Mut.to_chalk(&db), chalk_ir::Mutability::Mut);
+}
+
+#[test]
+fn variance_round_trip() {
+    let db = TestDB::default();
+    for &variance in &[Variance::Covariant, Variance::Invariant, Variance::Contravariant] {
+        assert_eq!(from_chalk::<Variance, _>(&db, variance.to_chalk(&db)), variance);
+    }
+    assert_eq!(Variance::Contravariant.to_chalk(&db), chalk_ir::Variance::Contravariant);
+}
+
+#[test]
+fn lifetime_from_chalk_handles_every_variant() {
+    let db = TestDB::default();
+    let bound = chalk_ir::BoundVar::new(DebruijnIndex::INNERMOST, 0);
+    let placeholder = chalk_ir::PlaceholderIndex { ui: chalk_ir::UniverseIndex::ROOT, idx: 0 };
+    let cases = vec![
+        (LifetimeData::BoundVar(bound), Lifetime::Erased),
+        (LifetimeData::InferenceVar(chalk_ir::InferenceVar::from(0)), Lifetime::Erased),
+        (LifetimeData::Placeholder(placeholder), Lifetime::Erased),
+        (LifetimeData::Static, Lifetime::Static),
+        (LifetimeData::Empty(chalk_ir::UniverseIndex::ROOT), Lifetime::Erased),
+        (LifetimeData::Erased, Lifetime::Erased),
+    ];
+    for (chalk, expected) in cases {
+        assert_eq!(from_chalk::<Lifetime, _>(&db, chalk.intern(&Interner)), expected);
+    }
+    assert_eq!(from_chalk::<Lifetime, _>(&db, Lifetime::Static.to_chalk(&db)), Lifetime::Static);
+}
+
+#[test]
+fn inherent_assoc_ty_has_no_trait() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+struct S;
+impl S {
+    type Assoc = u8;
+}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let def_map = module.def_map(&db);
+    let impl_ = def_map[module.local_id].scope.impls().next().unwrap();
+    let type_alias = db
+        .impl_data(impl_)
+        .items
+        .iter()
+        .find_map(|item| match item {
+            hir_def::AssocItemId::TypeAliasId(it) => Some(*it),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(assoc_ty_trait(&db, type_alias), None);
+
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let projection_ty =
+        ProjectionTy { associated_ty: type_alias, parameters: Substs::single(self_ty.clone()) };
+    let assoc = Ty::AssociatedType(type_alias, projection_ty.parameters.clone());
+    assert_eq!(assoc.associated_type_parent_trait(&db), None);
+    let pred = GenericPredicate::Projection(ProjectionPredicate {
+        projection_ty,
+        ty: Ty::Scalar(Scalar::Bool),
+    });
+    assert!(generic_predicate_to_inline_bound(&db, &pred, &self_ty).is_none());
+}
+
+#[test]
+fn inline_bound_leaves_out_self_of_three_arg_substs() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo<A, B> {}");
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let (a, b) = (Ty::Scalar(Scalar::Bool), Ty::Str);
+    let substs = Substs(vec![self_ty.clone(), a.clone(), b.clone()].into());
+    assert_eq!(substs.split_self(), Some((&self_ty, &[a.clone(), b.clone()][..])));
+    assert_eq!(Substs::empty().split_self(), None);
+
+    let pred = match implemented_by_bound_self(&db, file_id) {
+        GenericPredicate::Implemented(trait_ref) => {
+            GenericPredicate::Implemented(TraitRef { substs, ..trait_ref })
+        }
+        _ => unreachable!(),
+    };
+    match generic_predicate_to_inline_bound(&db, &pred, &self_ty) {
+        Some(rust_ir::InlineBound::TraitBound(bound)) => {
+            let args: Vec<GenericArg> =
+                bound.args_no_self.into_iter().map(|it| from_chalk(&db, it)).collect();
+            assert_eq!(args, vec![GenericArg::Ty(a), GenericArg::Ty(b)]);
+        }
+        bound => panic!("expected a trait bound, got {:?}", bound),
+    }
+    assert!(generic_predicate_to_inline_bound(&db, &pred, &Ty::Str).is_none());
+}
+
+#[test]
+fn benchmark_empty_substs_to_chalk() {
+    if skip_slow_tests() {
+        return;
+    }
+    let db = TestDB::default();
+    let substs = Substs::empty();
+    let n = 1_000_000;
+
+    let generic = {
+        let _pt = bench("empty substs via from_iter");
+        (0..n)
+            .filter(|_| {
+                let args = substs.iter().map(|ty| GenericArg::Ty(ty.clone()).to_chalk(&db));
+                chalk_ir::Substitution::from_iter(&Interner, args).is_empty(&Interner)
+            })
+            .count()
+    };
+    let fast_path = {
+        let _pt = bench("empty substs to_chalk");
+        (0..n).filter(|_| substs.clone().to_chalk(&db).is_empty(&Interner)).count()
+    };
+    assert_eq!(generic, n);
+    assert_eq!(fast_path, n);
+}
+
+fn ty_kind_name(ty: &chalk_ir::Ty<Interner>) -> &'static str {
+    // No wildcard, so that a new variant fails to compile until it's covered
+    // by `from_chalk_handles_every_ty_kind`.
+    match ty.kind(&Interner) {
+        chalk_ir::TyKind::Adt(..) => "Adt",
+        chalk_ir::TyKind::AssociatedType(..) => "AssociatedType",
+        chalk_ir::TyKind::Scalar(..) => "Scalar",
+        chalk_ir::TyKind::Tuple(..) => "Tuple",
+        chalk_ir::TyKind::Array(..) => "Array",
+        chalk_ir::TyKind::Slice(..) => "Slice",
+        chalk_ir::TyKind::Raw(..) => "Raw",
+        chalk_ir::TyKind::Ref(..) => "Ref",
+        chalk_ir::TyKind::OpaqueType(..) => "OpaqueType",
+        chalk_ir::TyKind::FnDef(..) => "FnDef",
+        chalk_ir::TyKind::Str => "Str",
+        chalk_ir::TyKind::Never => "Never",
+        chalk_ir::TyKind::Closure(..) => "Closure",
+        chalk_ir::TyKind::Generator(..) => "Generator",
+        chalk_ir::TyKind::GeneratorWitness(..) => "GeneratorWitness",
+        chalk_ir::TyKind::Foreign(..) => "Foreign",
+        chalk_ir::TyKind::Error => "Error",
+        chalk_ir::TyKind::Placeholder(..) => "Placeholder",
+        chalk_ir::TyKind::Dyn(..) => "Dyn",
+        chalk_ir::TyKind::Alias(..) => "Alias",
+        chalk_ir::TyKind::Function(..) => "Function",
+        chalk_ir::TyKind::BoundVar(..) => "BoundVar",
+        chalk_ir::TyKind::InferenceVar(..) => "InferenceVar",
+    }
+}
+
+/// A Chalk type of each `TyKind` variant.
+fn chalk_tys_of_every_kind() -> (TestDB, Vec<chalk_ir::Ty<Interner>>) {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+struct S;
+trait Tr { type Output; }
+fn generic<T>() -> impl Tr {
+    let f = || ();
+}
+"#,
+    );
+    let adt = db.first_adt(file_id);
+    let trait_ = db.first_trait(file_id);
+    let func = db.first_fn(file_id);
+    let closure = db
+        .infer(func.into())
+        .type_of_expr
+        .iter()
+        .map(|(_, ty)| ty.clone())
+        .find(|ty| ty.as_closure().is_some())
+        .unwrap();
+    let assoc_ty = db.trait_data(trait_).associated_type_by_name(&name![Output]).unwrap();
+    let s = Ty::Adt(adt, Substs::empty());
+    let unit = Ty::unit();
+    let projection =
+        ProjectionTy { associated_ty: assoc_ty, parameters: Substs::single(s.clone()) };
+    let generator_id = chalk_ir::GeneratorId(base_db::salsa::InternId::from(0u32));
+    let empty = chalk_ir::Substitution::empty(&Interner);
+
+    let tys = vec![
+        s.clone(),
+        Ty::AssociatedType(assoc_ty, Substs::single(s.clone())),
+        Ty::Scalar(Scalar::Bool),
+        Ty::Tuple(1, Substs::single(s.clone())),
+        Ty::Array(Substs::single(s.clone())),
+        Ty::Slice(Substs::single(s.clone())),
+        Ty::Raw(Mutability::Not, Substs::single(s.clone())),
+        Ty::Ref(Mutability::Not, Substs::single(s.clone())),
+        Ty::OpaqueType(OpaqueTyId::ReturnTypeImplTrait(func, 0), Substs::type_params(&db, func)),
+        Ty::FnDef(CallableDefId::FunctionId(func), Substs::type_params(&db, func)),
+        Ty::Str,
+        Ty::Never,
+        closure,
+        Ty::ForeignType(assoc_ty),
+        Ty::Unknown,
+        Substs::type_params(&db, func)[0].clone(),
+        Ty::Dyn(Arc::new([implemented_by_bound_self(&db, file_id)])),
+        Ty::Alias(AliasTy::Projection(projection)),
+        Ty::Function(FnPointer {
+            num_args: 0,
+            sig: FnSig { variadic: false, safety: Safety::Safe },
+            substs: Substs::single(unit),
+        }),
+        Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0)),
+    ]
+    .into_iter()
+    .map(|ty| ty.to_chalk(&db))
+    .chain(vec![
+        chalk_ir::TyKind::Generator(generator_id, empty.clone()).intern(&Interner),
+        chalk_ir::TyKind::GeneratorWitness(generator_id, empty).intern(&Interner),
+        chalk_ir::TyKind::InferenceVar(
+            chalk_ir::InferenceVar::from(0),
+            chalk_ir::TyVariableKind::General,
+        )
+        .intern(&Interner),
+    ])
+    .collect::<Vec<_>>();
+    (db, tys)
+}
+
+/// Checks that converting a Chalk type of `kind` gives a type which
+/// round-trips. The tests calling this are generated, one per `TyKind`.
+fn check_ty_kind_round_trips(kind: &str) {
+    let (db, tys) = chalk_tys_of_every_kind();
+    let chalk_ty = tys
+        .into_iter()
+        .find(|ty| ty_kind_name(ty) == kind)
+        .unwrap_or_else(|| panic!("no sample type of `TyKind::{}`", kind));
+    let ty: Ty = from_chalk(&db, chalk_ty);
+    assert_eq!(from_chalk::<Ty, _>(&db, ty.clone().to_chalk(&db)), unknown_as_error(ty));
+}
+
+/// `Ty::Unknown` goes to Chalk as its error type, which comes back as
+/// `Ty::Error`.
+fn unknown_as_error(ty: Ty) -> Ty {
+    ty.fold(&mut |ty| if ty == Ty::Unknown { Ty::Error } else { ty })
+}
+
+#[test]
+fn from_chalk_handles_every_ty_kind() {
+    let (db, tys) = chalk_tys_of_every_kind();
+    let mut names = tys.iter().map(ty_kind_name).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), 23, "expected one type per `TyKind` variant");
+
+    for ty in tys {
+        let _p = stdx::panic_context::enter(ty_kind_name(&ty).to_string());
+        from_chalk::<Ty, _>(&db, ty);
+    }
+}
+
+#[test]
+fn rpit_and_tait_opaque_ty_ids_are_distinct() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Tr {}
+impl Tr for () {}
+type Alias = impl Tr;
+fn rpit() -> impl Tr {}
+"#,
+    );
+    let alias = db.first_type_alias(file_id);
+    let func = db.first_fn(file_id);
+    let opaque_ty_id = |ty: &Ty| match ty {
+        Ty::Alias(AliasTy::Opaque(opaque_ty)) => opaque_ty.opaque_ty_id,
+        _ => panic!("expected an opaque type, got {:?}", ty),
+    };
+    let alias_ty = db.ty(alias.into()).value;
+    let tait = opaque_ty_id(&alias_ty);
+    let rpit = opaque_ty_id(db.callable_item_signature(func.into()).value.ret());
+    assert_eq!(tait, OpaqueTyId::TypeAliasImplTrait(alias, 0));
+    assert_eq!(rpit, OpaqueTyId::ReturnTypeImplTrait(func, 0));
+    assert_eq!(alias_ty.display(&db).to_string(), "impl Tr");
+
+    let (chalk_tait, chalk_rpit) = (tait.to_chalk(&db), rpit.to_chalk(&db));
+    assert_ne!(chalk_tait, chalk_rpit);
+    assert_eq!(from_chalk::<OpaqueTyId, _>(&db, chalk_tait), tait);
+    assert_eq!(from_chalk::<OpaqueTyId, _>(&db, chalk_rpit), rpit);
+}
+
+#[test]
+fn opaque_alias_eq_from_chalk() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Iterator { type Item; }
+fn rpit() -> impl Iterator<Item = u32> {}
+"#,
+    );
+    let func = db.first_fn(file_id);
+    let opaque_ty = match db.callable_item_signature(func.into()).value.ret() {
+        Ty::Alias(AliasTy::Opaque(opaque_ty)) => opaque_ty.clone(),
+        ty => panic!("expected an opaque type, got {:?}", ty),
+    };
+    let hidden_ty = Ty::Tuple(0, Substs::empty());
+    let alias_eq = chalk_ir::AliasEq {
+        alias: chalk_ir::AliasTy::Opaque(chalk_ir::OpaqueTy {
+            opaque_ty_id: opaque_ty.opaque_ty_id.to_chalk(&db),
+            substitution: opaque_ty.parameters.clone().to_chalk(&db),
+        }),
+        ty: hidden_ty.clone().to_chalk(&db),
+    };
+    let where_clause =
+        chalk_ir::Binders::empty(&Interner, chalk_ir::WhereClause::AliasEq(alias_eq));
+
+    let pred: GenericPredicate = from_chalk(&db, Some(where_clause.clone()));
+    assert_eq!(pred, GenericPredicate::Opaque(OpaqueTyPredicate { opaque_ty, ty: hidden_ty }));
+    assert_eq!(pred.display(&db).to_string(), "impl Iterator<Item = u32> = ()");
+    assert_eq!(pred.trait_ref(&db), None);
+    assert_eq!(pred.clone().to_chalk(&db), Some(where_clause));
+    assert_eq!(Obligation::from_predicate(pred), None);
+}
+
+#[test]
+fn bound_vars_in_containers_keep_their_index() {
+    let db = TestDB::default();
+    for depth in 0..3 {
+        let bound_var = crate::BoundVar::new(DebruijnIndex::new(depth), 1);
+        let bound = Ty::BoundVar(bound_var);
+        let containers = vec![
+            Ty::Tuple(2, Substs(vec![Ty::Scalar(Scalar::Bool), bound.clone()].into())),
+            Ty::Array(Substs::single(bound.clone())),
+            Ty::Slice(Substs::single(bound.clone())),
+            Ty::Ref(Mutability::Not, Substs::single(bound.clone())),
+            Ty::Raw(Mutability::Mut, Substs::single(bound.clone())),
+        ];
+        for ty in containers {
+            let chalk_ty = ty.clone().to_chalk(&db);
+            let inner = match chalk_ty.kind(&Interner) {
+                chalk_ir::TyKind::Tuple(_, substs) => {
+                    substs.at(&Interner, 1).assert_ty_ref(&Interner)
+                }
+                chalk_ir::TyKind::Array(ty, _)
+                | chalk_ir::TyKind::Slice(ty)
+                | chalk_ir::TyKind::Ref(_, _, ty)
+                | chalk_ir::TyKind::Raw(_, ty) => ty,
+                _ => panic!("unexpected kind: {:?}", chalk_ty),
+            };
+            assert_eq!(inner.bound_var(&Interner), Some(bound_var), "{:?}", ty);
+            assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), ty);
+        }
+    }
+}
+
+#[test]
+fn sample_tys_round_trip() {
+    let db = TestDB::default();
+    for (spec, ty) in fixtures::sample_tys() {
+        let chalk_ty = ty.clone().to_chalk(&db);
+        assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), unknown_as_error(ty), "{}", spec);
+    }
+}
+
+#[test]
+fn foreign_type_in_generic_position_round_trips() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+extern "C" {
+    type Foreign;
+    type Generic<T>;
+}
+struct S<T>(T);
+"#,
+    );
+    let adt = db.first_adt(file_id);
+    let mut foreign_types = db.type_aliases(file_id);
+    foreign_types.sort_by_key(|&it| db.type_alias_data(it).name.to_string());
+    let (foreign, generic) = (foreign_types[0], foreign_types[1]);
+
+    let foreign_ty = db.ty(foreign.into());
+    assert_eq!(foreign_ty.num_binders, 0);
+    let reference = Ty::Ref(Mutability::Not, Substs::single(foreign_ty.value));
+    let ty = Ty::Adt(adt, Substs::single(reference));
+    assert_eq!(from_chalk::<Ty, _>(&db, ty.clone().to_chalk(&db)), ty);
+
+    // generic parameters on a foreign type are an error, and are ignored
+    let generic_ty = db.ty(generic.into());
+    assert_eq!(generic_ty.num_binders, 0);
+    assert_eq!(generic_ty.value.substs(), None);
+    assert_eq!(from_chalk::<Ty, _>(&db, generic_ty.value.clone().to_chalk(&db)), generic_ty.value);
+}
+
+#[test]
+fn projection_and_associated_type_stay_distinct() {
+    let (db, file_id) = TestDB::with_single_file("trait Tr { type Output; }");
+    let trait_ = db.first_trait(file_id);
+    let type_alias = db.trait_data(trait_).associated_type_by_name(&name![Output]).unwrap();
+    let parameters = Substs::single(Ty::Scalar(Scalar::Bool));
+    let projection =
+        Ty::Alias(AliasTy::Projection(ProjectionTy { associated_ty: type_alias, parameters }));
+    let assoc = Ty::AssociatedType(type_alias, Substs::single(Ty::Scalar(Scalar::Bool)));
+
+    let chalk_projection = projection.clone().to_chalk(&db);
+    let chalk_assoc = assoc.clone().to_chalk(&db);
+    assert!(matches!(
+        chalk_projection.kind(&Interner),
+        chalk_ir::TyKind::Alias(chalk_ir::AliasTy::Projection(_))
+    ));
+    assert!(matches!(chalk_assoc.kind(&Interner), chalk_ir::TyKind::AssociatedType(..)));
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_projection), projection);
+    assert_eq!(from_chalk::<Ty, _>(&db, chalk_assoc), assoc);
+}
+
+/// Lowering `T::Output` gives a projection; when Chalk can't normalize it any
+/// further, it hands it back as an associated type.
+#[test]
+fn projections_normalize_to_associated_types() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Tr { type Output; }
+fn output<T: Tr>() -> T::Output { loop {} }
+fn test<T: Tr>() { output::<T>(); }
+"#,
+    );
+    let funcs = db.fns(file_id);
+    let (output, test) = match funcs[..] {
+        [output, test] => (output, test),
+        _ => panic!("expected two functions"),
+    };
+
+    let sig = db.callable_item_signature(output.into());
+    assert!(matches!(sig.value.ret(), Ty::Alias(AliasTy::Projection(_))));
+
+    let infer = db.infer(test.into());
+    let call_ty = infer
+        .type_of_expr
+        .iter()
+        .map(|(_, ty)| ty)
+        .find(|ty| !matches!(ty, Ty::FnDef(..) | Ty::Tuple(0, _)))
+        .expect("no call in fixture");
+    assert!(matches!(call_ty, Ty::AssociatedType(..)), "{:?}", call_ty);
+}
+
+#[test]
+fn dyn_predicate_order_does_not_matter() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait A {}
+trait B {}
+auto trait Send {}
+"#,
+    );
+    let traits = db.traits(file_id);
+    let self_ty = Ty::BoundVar(crate::BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let implemented = |trait_| {
+        GenericPredicate::Implemented(TraitRef { trait_, substs: Substs::single(self_ty.clone()) })
+    };
+    let (auto, principal): (Vec<_>, Vec<_>) =
+        traits.into_iter().partition(|&it| db.trait_data(it).auto);
+    let (a, b, send) = match (&principal[..], &auto[..]) {
+        (&[a, b], &[send]) => (implemented(a), implemented(b), implemented(send)),
+        _ => panic!("expected two traits and an auto trait"),
+    };
+
+    let a_send = Ty::Dyn(vec![a.clone(), send.clone()].into());
+    let send_a = Ty::Dyn(vec![send, a.clone()].into());
+    let chalk_ty = a_send.clone().to_chalk(&db);
+    assert_eq!(send_a.to_chalk(&db), chalk_ty);
+    let back = from_chalk::<Ty, _>(&db, chalk_ty);
+    assert_eq!(back, a_send);
+    assert_eq!(back.dyn_trait(), Some(principal[0]));
+
+    let a_b = Ty::Dyn(vec![a.clone(), b.clone()].into());
+    let b_a = Ty::Dyn(vec![b, a].into());
+    assert_eq!(a_b.to_chalk(&db), b_a.to_chalk(&db));
+}
+
+#[test]
+fn boxed_dyn_with_assoc_type_binding_round_trips() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Iterator { type Item; }
+struct Box<T>;
+fn f(it: Box<dyn Iterator<Item = u8>>, nested: Box<dyn Iterator<Item = Box<dyn Iterator<Item = u8>>>>) {}
+"#,
+    );
+    let func = db.first_fn(file_id);
+    let sig = db.callable_item_signature(func.into());
+    let item_ty = |boxed: &Ty| match boxed.substs().unwrap()[0].as_dyn_predicates() {
+        Some([GenericPredicate::Implemented(_), GenericPredicate::Projection(proj)]) => {
+            proj.ty.clone()
+        }
+        preds => panic!("unexpected predicates {:?}", preds),
+    };
+    let (boxed, nested) = (&sig.value.params()[0], &sig.value.params()[1]);
+    assert_eq!(item_ty(boxed), Ty::Scalar(Scalar::Uint(UintTy::U8)));
+    assert_eq!(&item_ty(nested), boxed);
+
+    for ty in &[boxed, nested] {
+        let chalk_ty = (*ty).clone().to_chalk(&db);
+        assert_eq!(&from_chalk::<Ty, _>(&db, chalk_ty), *ty);
+    }
+}
+
+#[test]
+fn dyn_without_principal_round_trips() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+auto trait Send {}
+auto trait Sync {}
+struct Box<T: ?Sized>(T);
+fn f(a: Box<dyn Send + Sync>, b: Box<dyn Sync + Send>) {}
+"#,
+    );
+    let func = db.first_fn(file_id);
+    let sig = db.callable_item_signature(func.into()).value;
+    let (send_sync, sync_send) = (sig.params()[0].clone(), sig.params()[1].clone());
+    let predicates = match &send_sync {
+        Ty::Adt(_, substs) => substs.as_single().as_dyn_predicates().unwrap().to_vec(),
+        ty => panic!("expected a box, got {:?}", ty),
+    };
+    assert_eq!(predicates.len(), 2);
+    assert!(predicates.iter().all(|pred| match pred {
+        GenericPredicate::Implemented(trait_ref) => db.trait_data(trait_ref.trait_).auto,
+        _ => false,
+    }));
+
+    let chalk_ty = send_sync.clone().to_chalk(&db);
+    assert_eq!(sync_send.to_chalk(&db), chalk_ty);
+    let back: Ty = from_chalk(&db, chalk_ty);
+    assert_eq!(back.display(&db).to_string(), "Box<dyn Send + Sync>");
+    assert_eq!(from_chalk::<Ty, _>(&db, back.clone().to_chalk(&db)), back);
+}
+
+#[test]
+fn dyn_with_only_error_predicates_is_an_error() {
+    let db = TestDB::default();
+    for predicates in [vec![], vec![GenericPredicate::Error, GenericPredicate::Error]].iter() {
+        let dyn_ty = Ty::Dyn(predicates.clone().into());
+        let chalk_ty = dyn_ty.to_chalk(&db);
+        assert_eq!(chalk_ty.kind(&Interner), &chalk_ir::TyKind::Error);
+        assert_eq!(from_chalk::<Ty, _>(&db, chalk_ty), Ty::Error);
+    }
+}
+
+#[cfg(feature = "chalk-timing")]
+#[test]
+fn to_chalk_emits_spans() {
+    use std::sync::Mutex;
+    use tracing::{field, span, Event, Metadata, Subscriber};
+
+    /// Records the `what` of each `to_chalk` span.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl field::Visit for Recorder {
+        fn record_str(&mut self, field: &field::Field, value: &str) {
+            if field.name() == "what" {
+                self.0.lock().unwrap().push(value.to_string());
+            }
+        }
+        fn record_debug(&mut self, _field: &field::Field, _value: &dyn fmt::Debug) {}
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            if span.metadata().name() == "to_chalk" {
+                span.record(&mut self.clone());
+            }
+            span::Id::from_u64(1)
+        }
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+        fn event(&self, _event: &Event<'_>) {}
+        fn enter(&self, _span: &span::Id) {}
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    let db = TestDB::default();
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        Ty::Tuple(1, Substs::single(Ty::Str)).to_chalk(&db);
+        Arc::new(TraitEnvironment { predicates: Vec::new() }).to_chalk(&db);
+    });
+    let spans = recorder.0.lock().unwrap().clone();
+    assert_eq!(spans, vec!["Ty", "Substs", "Ty", "TraitEnvironment"]);
+}
+
+#[test]
+fn tuple_with_wrong_cardinality_from_chalk_is_rejected() {
+    assert_eq!(tuple_substs_from_chalk(2, Substs::single(Ty::Str)), None);
+    assert_eq!(tuple_substs_from_chalk(1, Substs::single(Ty::Str)), Some(Substs::single(Ty::Str)));
+}
+
+#[test]
+fn strip_references_counts_peeled_refs() {
+    let i32_ty = Ty::Scalar(Scalar::Int(crate::primitive::IntTy::I32));
+    let ty = Ty::Ref(
+        Mutability::Not,
+        Substs::single(Ty::Ref(Mutability::Mut, Substs::single(i32_ty.clone()))),
+    );
+    assert_eq!(ty.strip_references(), (&i32_ty, 2));
+    assert_eq!(i32_ty.strip_references(), (&i32_ty, 0));
+
+    let db = TestDB::default();
+    let round_tripped: Ty = from_chalk(&db, ty.clone().to_chalk(&db));
+    assert_eq!(round_tripped.strip_references(), (&i32_ty, 2));
+}
+
+#[test]
+fn map_types_maps_each_type_arg() {
+    let (db, file_id) = TestDB::with_single_file("struct Foo<'a, T, const N: usize>(&'a [T; N]);");
+    let strukt = db.first_struct(file_id);
+    // `'a` and `N` are erased, so only `T` has an argument
+    let substs = Substs::build_for_def(&db, strukt).fill_with_unknown().build();
+    assert_eq!(substs.0[..], [Ty::Unknown]);
+
+    let u8_ty = Ty::Scalar(Scalar::Uint(crate::primitive::UintTy::U8));
+    let mapped = substs.map_types(|ty| match ty {
+        Ty::Unknown => u8_ty.clone(),
+        _ => ty.clone(),
+    });
+    assert_eq!(mapped, Substs::single(u8_ty.clone()));
+
+    let nested = Substs(vec![Ty::Unknown, Ty::Slice(Substs::single(Ty::Unknown))].into());
+    let mapped = nested.map_types(|ty| if ty.is_unknown() { u8_ty.clone() } else { ty.clone() });
+    assert_eq!(mapped.0[..], [u8_ty, Ty::Slice(Substs::single(Ty::Unknown))]);
+}
+
+#[test]
+fn subst_bound_vars_substitutes_type_args() {
+    let (db, file_id) = TestDB::with_single_file("struct Foo<'a, T, const N: usize>(&'a [T; N]);");
+    let strukt = db.first_struct(file_id);
+    let field_types = db.field_types(strukt.into());
+    let field_ty = field_types.iter().next().unwrap().1.clone();
+    // `'a` and `N` are erased, so the only argument is the one for `T`
+    let u8_ty = Ty::Scalar(Scalar::Uint(crate::primitive::UintTy::U8));
+    let substs = Substs::single(u8_ty.clone());
+
+    let ty = field_ty.value.clone().subst_bound_vars(&substs);
+    assert_eq!(ty, field_ty.subst(&substs));
+    assert_eq!(
+        ty,
+        Ty::Ref(Mutability::Not, Substs::single(Ty::Array(Substs::single(u8_ty.clone()))))
+    );
+    // the erased lifetime and length come back as the placeholders we use for them
+    assert_eq!(ty.display(&db).to_string(), "&[u8; _]");
+    let chalk_ty = ty.to_chalk(&db);
+    match chalk_ty.kind(&Interner) {
+        chalk_ir::TyKind::Ref(_, lifetime, _) => {
+            assert!(matches!(lifetime.data(&Interner), LifetimeData::Static))
+        }
+        kind => panic!("expected a reference, got {:?}", kind),
+    }
+}
+
+#[test]
+fn too_short_substs_convert_as_unknown() {
+    let db = TestDB::default();
+    assert_eq!(Substs::empty().get_or_unknown(0), Ty::Unknown);
+
+    let empty = Substs::empty;
+    let tys = vec![
+        Ty::Ref(Mutability::Not, empty()),
+        Ty::Raw(Mutability::Mut, empty()),
+        Ty::Slice(empty()),
+        Ty::Array(empty()),
+    ];
+    for ty in tys {
+        let back: Ty = from_chalk(&db, ty.clone().to_chalk(&db));
+        // the missing type goes to Chalk as unknown and comes back as an error
+        let expected = match ty {
+            Ty::Ref(m, _) => Ty::Ref(m, Substs::single(Ty::Error)),
+            Ty::Raw(m, _) => Ty::Raw(m, Substs::single(Ty::Error)),
+            Ty::Slice(_) => Ty::Slice(Substs::single(Ty::Error)),
+            Ty::Array(_) => Ty::Array(Substs::single(Ty::Error)),
+            _ => unreachable!(),
+        };
+        assert_eq!(back, expected);
+    }
+}
@@ -0,0 +1,155 @@
+//! Generated file, do not edit by hand, see `xtask/src/codegen`
+
+/// The types in `fixtures.txt`, each with its line there.
+pub(super) fn sample_tys() -> Vec<(&'static str, crate::Ty)> {
+    vec![
+        ("_", crate::Ty::Unknown),
+        ("!", crate::Ty::Never),
+        ("bool", crate::Ty::Scalar(crate::Scalar::Bool)),
+        ("char", crate::Ty::Scalar(crate::Scalar::Char)),
+        ("u8", crate::Ty::Scalar(crate::Scalar::Uint(crate::primitive::UintTy::U8))),
+        ("i128", crate::Ty::Scalar(crate::Scalar::Int(crate::primitive::IntTy::I128))),
+        ("usize", crate::Ty::Scalar(crate::Scalar::Uint(crate::primitive::UintTy::Usize))),
+        ("f64", crate::Ty::Scalar(crate::Scalar::Float(crate::primitive::FloatTy::F64))),
+        ("str", crate::Ty::Str),
+        ("()", crate::Ty::Tuple(0, crate::Substs::empty())),
+        (
+            "(bool, str)",
+            crate::Ty::Tuple(
+                2,
+                crate::Substs(vec![crate::Ty::Scalar(crate::Scalar::Bool), crate::Ty::Str].into()),
+            ),
+        ),
+        (
+            "[u8]",
+            crate::Ty::Slice(crate::Substs::single(crate::Ty::Scalar(crate::Scalar::Uint(
+                crate::primitive::UintTy::U8,
+            )))),
+        ),
+        (
+            "[u8; _]",
+            crate::Ty::Array(crate::Substs::single(crate::Ty::Scalar(crate::Scalar::Uint(
+                crate::primitive::UintTy::U8,
+            )))),
+        ),
+        (
+            "&u32",
+            crate::Ty::Ref(
+                crate::Mutability::Not,
+                crate::Substs::single(crate::Ty::Scalar(crate::Scalar::Uint(
+                    crate::primitive::UintTy::U32,
+                ))),
+            ),
+        ),
+        (
+            "&mut [char]",
+            crate::Ty::Ref(
+                crate::Mutability::Mut,
+                crate::Substs::single(crate::Ty::Slice(crate::Substs::single(crate::Ty::Scalar(
+                    crate::Scalar::Char,
+                )))),
+            ),
+        ),
+        (
+            "*const u8",
+            crate::Ty::Raw(
+                crate::Mutability::Not,
+                crate::Substs::single(crate::Ty::Scalar(crate::Scalar::Uint(
+                    crate::primitive::UintTy::U8,
+                ))),
+            ),
+        ),
+        (
+            "*mut *const u8",
+            crate::Ty::Raw(
+                crate::Mutability::Mut,
+                crate::Substs::single(crate::Ty::Raw(
+                    crate::Mutability::Not,
+                    crate::Substs::single(crate::Ty::Scalar(crate::Scalar::Uint(
+                        crate::primitive::UintTy::U8,
+                    ))),
+                )),
+            ),
+        ),
+        (
+            "fn()",
+            crate::Ty::Function(crate::FnPointer {
+                num_args: 0,
+                sig: crate::FnSig { variadic: false, safety: crate::Safety::Safe },
+                substs: crate::Substs(vec![crate::Ty::unit()].into()),
+            }),
+        ),
+        (
+            "fn(u8, bool) -> str",
+            crate::Ty::Function(crate::FnPointer {
+                num_args: 2,
+                sig: crate::FnSig { variadic: false, safety: crate::Safety::Safe },
+                substs: crate::Substs(
+                    vec![
+                        crate::Ty::Scalar(crate::Scalar::Uint(crate::primitive::UintTy::U8)),
+                        crate::Ty::Scalar(crate::Scalar::Bool),
+                        crate::Ty::Str,
+                    ]
+                    .into(),
+                ),
+            }),
+        ),
+        (
+            "unsafe fn(u8, ...) -> !",
+            crate::Ty::Function(crate::FnPointer {
+                num_args: 1,
+                sig: crate::FnSig { variadic: true, safety: crate::Safety::Unsafe },
+                substs: crate::Substs(
+                    vec![
+                        crate::Ty::Scalar(crate::Scalar::Uint(crate::primitive::UintTy::U8)),
+                        crate::Ty::Never,
+                    ]
+                    .into(),
+                ),
+            }),
+        ),
+        ("^0.0", crate::Ty::BoundVar(crate::BoundVar::new(chalk_ir::DebruijnIndex::new(0), 0))),
+        (
+            "(^0.1, &^2.0)",
+            crate::Ty::Tuple(
+                2,
+                crate::Substs(
+                    vec![
+                        crate::Ty::BoundVar(crate::BoundVar::new(
+                            chalk_ir::DebruijnIndex::new(0),
+                            1,
+                        )),
+                        crate::Ty::Ref(
+                            crate::Mutability::Not,
+                            crate::Substs::single(crate::Ty::BoundVar(crate::BoundVar::new(
+                                chalk_ir::DebruijnIndex::new(2),
+                                0,
+                            ))),
+                        ),
+                    ]
+                    .into(),
+                ),
+            ),
+        ),
+        (
+            "fn(^0.0) -> ^1.0",
+            crate::Ty::Function(crate::FnPointer {
+                num_args: 1,
+                sig: crate::FnSig { variadic: false, safety: crate::Safety::Safe },
+                substs: crate::Substs(
+                    vec![
+                        crate::Ty::BoundVar(crate::BoundVar::new(
+                            chalk_ir::DebruijnIndex::new(0),
+                            0,
+                        )),
+                        crate::Ty::BoundVar(crate::BoundVar::new(
+                            chalk_ir::DebruijnIndex::new(1),
+                            0,
+                        )),
+                    ]
+                    .into(),
+                ),
+            }),
+        ),
+    ]
+}
@@ -0,0 +1,118 @@
+//! Generated file, do not edit by hand, see `xtask/src/codegen`
+
+use super::check_ty_kind_round_trips;
+
+#[test]
+fn adt_round_trips() {
+    check_ty_kind_round_trips("Adt");
+}
+
+#[test]
+fn associated_type_round_trips() {
+    check_ty_kind_round_trips("AssociatedType");
+}
+
+#[test]
+fn scalar_round_trips() {
+    check_ty_kind_round_trips("Scalar");
+}
+
+#[test]
+fn tuple_round_trips() {
+    check_ty_kind_round_trips("Tuple");
+}
+
+#[test]
+fn array_round_trips() {
+    check_ty_kind_round_trips("Array");
+}
+
+#[test]
+fn slice_round_trips() {
+    check_ty_kind_round_trips("Slice");
+}
+
+#[test]
+fn raw_round_trips() {
+    check_ty_kind_round_trips("Raw");
+}
+
+#[test]
+fn ref_round_trips() {
+    check_ty_kind_round_trips("Ref");
+}
+
+#[test]
+fn opaque_type_round_trips() {
+    check_ty_kind_round_trips("OpaqueType");
+}
+
+#[test]
+fn fn_def_round_trips() {
+    check_ty_kind_round_trips("FnDef");
+}
+
+#[test]
+fn str_round_trips() {
+    check_ty_kind_round_trips("Str");
+}
+
+#[test]
+fn never_round_trips() {
+    check_ty_kind_round_trips("Never");
+}
+
+#[test]
+fn closure_round_trips() {
+    check_ty_kind_round_trips("Closure");
+}
+
+#[test]
+fn generator_round_trips() {
+    check_ty_kind_round_trips("Generator");
+}
+
+#[test]
+fn generator_witness_round_trips() {
+    check_ty_kind_round_trips("GeneratorWitness");
+}
+
+#[test]
+fn foreign_round_trips() {
+    check_ty_kind_round_trips("Foreign");
+}
+
+#[test]
+fn error_round_trips() {
+    check_ty_kind_round_trips("Error");
+}
+
+#[test]
+fn placeholder_round_trips() {
+    check_ty_kind_round_trips("Placeholder");
+}
+
+#[test]
+fn dyn_round_trips() {
+    check_ty_kind_round_trips("Dyn");
+}
+
+#[test]
+fn alias_round_trips() {
+    check_ty_kind_round_trips("Alias");
+}
+
+#[test]
+fn function_round_trips() {
+    check_ty_kind_round_trips("Function");
+}
+
+#[test]
+fn bound_var_round_trips() {
+    check_ty_kind_round_trips("BoundVar");
+}
+
+#[test]
+fn inference_var_round_trips() {
+    check_ty_kind_round_trips("InferenceVar");
+}
@@ -5,8 +5,8 @@ use chalk_ir::{AliasTy, GenericArg, Goal, Goals, Lifetime, ProgramClauseImplicat
 use itertools::Itertools;
 
 use super::{from_chalk, Interner, TypeAliasAsAssocType};
-use crate::{db::HirDatabase, CallableDefId};
-use hir_def::{AdtId, AssocContainerId, Lookup, TypeAliasId};
+use crate::{db::HirDatabase, utils::assoc_ty_trait, CallableDefId};
+use hir_def::{AdtId, TypeAliasId};
 
 pub(crate) use unsafe_tls::{set_current_program, with_current_program};
 
@@ -43,12 +43,13 @@ impl DebugContext<'_> {
     ) -> Result<(), fmt::Error> {
         let type_alias: TypeAliasId = from_chalk::<TypeAliasAsAssocType, _>(self.0, id).0;
         let type_alias_data = self.0.type_alias_data(type_alias);
-        let trait_ = match type_alias.lookup(self.0.upcast()).container {
-            AssocContainerId::TraitId(t) => t,
-            _ => panic!("associated type not in trait"),
-        };
-        let trait_data = self.0.trait_data(trait_);
-        write!(fmt, "{}::{}", trait_data.name, type_alias_data.name)
+        match assoc_ty_trait(self.0.upcast(), type_alias) {
+            Some(trait_) => {
+                let trait_data = self.0.trait_data(trait_);
+                write!(fmt, "{}::{}", trait_data.name, type_alias_data.name)
+            }
+            None => write!(fmt, "{}", type_alias_data.name),
+        }
     }
 
     pub(crate) fn debug_opaque_ty_id(
@@ -78,12 +79,14 @@ impl DebugContext<'_> {
         let type_alias: TypeAliasId =
             from_chalk::<TypeAliasAsAssocType, _>(self.0, projection_ty.associated_ty_id).0;
         let type_alias_data = self.0.type_alias_data(type_alias);
-        let trait_ = match type_alias.lookup(self.0.upcast()).container {
-            AssocContainerId::TraitId(t) => t,
-            _ => panic!("associated type not in trait"),
+        let params = projection_ty.substitution.as_slice(&Interner);
+        let trait_ = match assoc_ty_trait(self.0.upcast(), type_alias) {
+            Some(it) => it,
+            None => {
+                return write!(fmt, "<{:?}>::{}", &params[0], type_alias_data.name);
+            }
         };
         let trait_data = self.0.trait_data(trait_);
-        let params = projection_ty.substitution.as_slice(&Interner);
         write!(fmt, "<{:?} as {}", &params[0], trait_data.name,)?;
         if params.len() > 1 {
             write!(
@@ -0,0 +1,354 @@
+use base_db::fixture::WithFixture;
+use hir_def::db::DefDatabase;
+use hir_expand::name::name;
+
+use crate::{
+    db::HirDatabase, infer::unify::InferenceTable, test_db::TestDB, CallableDefId, Mutability,
+    Scalar, TyVariableKind,
+};
+
+use super::*;
+
+#[test]
+fn bound_from_def_predicates_is_provable() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Foo {}
+fn f<T: Foo, U>() {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let trait_ = db.first_trait(file_id);
+    let func = db.first_fn(file_id);
+    let params = Substs::type_params(&db, func);
+
+    let solve = |self_ty: &Ty| {
+        let trait_ref = TraitRef { trait_, substs: Substs::single(self_ty.clone()) };
+        let goal = in_env_for_def(&db, func.into(), Obligation::Trait(trait_ref));
+        db.trait_solve(module.krate(), Canonical::new(goal, None))
+    };
+    assert!(matches!(solve(&params[0]), Some(Solution::Unique(_))));
+    assert!(solve(&params[1]).is_none());
+}
+
+#[test]
+fn chalk_program_dump_contains_impl_clauses() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+struct S;
+trait Foo {}
+impl Foo for S {}
+"#,
+    );
+    let krate = db.module_for_file(file_id).krate();
+    let dump = db.dump_chalk_program(krate);
+    assert_eq!(dump, "for<> Implemented(S<[]>: Foo)\n");
+}
+
+#[test]
+fn impl_clause_dump_shows_trait_and_self_type() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+struct S;
+trait Foo {}
+impl Foo for S {}
+impl S {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let def_map = module.def_map(&db);
+    let impls = def_map[module.local_id].scope.impls().collect::<Vec<_>>();
+    let (trait_impl, inherent_impl) = match impls[..] {
+        [trait_impl, inherent_impl] => (trait_impl, inherent_impl),
+        _ => panic!("expected two impls, got {:?}", impls),
+    };
+    let dump = db.dump_impl_clause(trait_impl);
+    assert!(dump.contains("S<[]>: Foo"), "{}", dump);
+    assert_eq!(db.dump_impl_clause(inherent_impl), "");
+}
+
+#[test]
+fn in_environment_exposes_assumptions_and_goal() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Foo {}
+fn f<T: Foo>() {}
+"#,
+    );
+    let func = db.first_fn(file_id);
+    let bound =
+        db.generic_predicates(func.into())[0].clone().subst(&Substs::type_params(&db, func));
+    let goal = Obligation::from_predicate(bound.clone()).unwrap();
+
+    let mut in_env = in_env_for_def(&db, func.into(), goal.clone());
+    Arc::make_mut(&mut in_env.environment).predicates.push(GenericPredicate::Error);
+    let (assumptions, in_env_goal) = in_env.assumptions_and_goal();
+    assert_eq!(assumptions, vec![bound]);
+    assert_eq!(in_env_goal, &goal);
+}
+
+#[test]
+fn solver_config_limits_goal_size() {
+    let (mut db, file_id) = TestDB::with_single_file(
+        r#"
+struct S;
+struct W<T>(T);
+trait Foo {}
+impl Foo for S {}
+impl<T: Foo> Foo for W<T> {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let trait_ = db.first_trait(file_id);
+    let structs = db.adts(file_id);
+    let (s, w) = (structs[0], structs[1]);
+    let mut ty = Ty::Adt(s, Substs::empty());
+    for _ in 0..5 {
+        ty = Ty::Adt(w, Substs::single(ty));
+    }
+    let trait_ref = TraitRef { trait_, substs: Substs::single(ty) };
+    let env = Arc::new(TraitEnvironment { predicates: Vec::new() });
+    let goal = InEnvironment::new(env, Obligation::Trait(trait_ref));
+    let goal = Canonical::new(goal, None);
+
+    let solve = |db: &TestDB| db.trait_solve(module.krate(), goal.clone());
+    assert!(matches!(solve(&db), Some(Solution::Unique(_))));
+    db.set_solver_config(SolverConfig { max_size: 3, ..SolverConfig::default() });
+    assert!(matches!(solve(&db), Some(Solution::Ambig(_))));
+}
+
+#[test]
+fn type_params_is_identity_substitution() {
+    let (db, file_id) = TestDB::with_single_file("fn f<T, U>() {}");
+    let func = db.first_fn(file_id);
+    let params = crate::utils::generics(&db, func.into());
+    let expected = params.iter().map(|(id, _)| Ty::Placeholder(id)).collect::<Vec<_>>();
+
+    let substs = Substs::type_params(&db, func);
+    assert_eq!(substs.len(), 2);
+    assert_eq!(&substs[..], &expected[..]);
+}
+
+#[test]
+fn alpha_equivalent_goals_have_the_same_cache_key() {
+    let (db, file_id) = TestDB::with_single_file("trait Foo<T> {}");
+    let trait_ = db.first_trait(file_id);
+    let mut table = InferenceTable::new();
+    let vars = (0..8).map(|_| table.new_type_var()).collect::<Vec<_>>();
+    let var = |idx: usize| vars[idx].clone();
+    let key = |self_ty: Ty, arg: Ty| {
+        let trait_ref = TraitRef { trait_, substs: Substs(vec![self_ty, arg].into()) };
+        let env = Arc::new(TraitEnvironment { predicates: Vec::new() });
+        goal_cache_key(&db, &InEnvironment::new(env, Obligation::Trait(trait_ref)))
+    };
+
+    assert_eq!(key(var(3), var(7)), key(var(5), var(1)));
+    assert_ne!(key(var(3), var(7)), key(var(3), var(3)));
+}
+
+#[test]
+fn impl_trait_ref_chalk_converts_the_impl_header() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Tr<T> {}
+struct S<U>;
+impl<U> Tr<u8> for S<U> {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let def_map = module.def_map(&db);
+    let impl_ = def_map[module.local_id].scope.impls().next().unwrap();
+    let trait_ = db.first_trait(file_id);
+    let adt = db.first_adt(file_id);
+
+    let trait_ref = db.impl_trait_ref_chalk(impl_).unwrap();
+    assert_eq!(trait_ref.num_binders, 1);
+    assert_eq!(trait_id_from_chalk(trait_ref.value.trait_id), trait_);
+    let u = Ty::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    assert_eq!(
+        trait_ref.value.self_type_parameter(&Interner),
+        Ty::Adt(adt, Substs::single(u)).to_chalk(&db)
+    );
+}
+
+#[test]
+fn trait_solve_unless_unknown_skips_goals_over_unknown_types() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+struct S<T>;
+trait Foo {}
+impl<T> Foo for T {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let trait_ = db.first_trait(file_id);
+    let adt = db.first_adt(file_id);
+    let goal = |self_ty: Ty| {
+        let trait_ref = TraitRef { trait_, substs: Substs::single(self_ty) };
+        let env = Arc::new(TraitEnvironment { predicates: Vec::new() });
+        Canonical::new(InEnvironment::new(env, Obligation::Trait(trait_ref)), None)
+    };
+
+    let known = Ty::Adt(adt, Substs::single(Ty::Scalar(crate::Scalar::Bool)));
+    let unknown = Ty::Adt(adt, Substs::single(Ty::Unknown));
+    assert!(!known.contains_unknown());
+    assert!(unknown.contains_unknown());
+    let solution = trait_solve_unless_unknown(&db, module.krate(), goal(known));
+    assert!(matches!(solution, Some(Solution::Unique(_))));
+    // the blanket impl applies, but Chalk is only asked by `trait_solve`
+    let solution = trait_solve_unless_unknown(&db, module.krate(), goal(unknown.clone()));
+    assert!(matches!(solution, Some(Solution::Ambig(Guidance::Unknown))));
+    assert!(matches!(db.trait_solve(module.krate(), goal(unknown)), Some(Solution::Unique(_))));
+}
+
+#[test]
+fn solved_borrow_has_no_lifetime_var() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+struct S;
+trait Tr { type Output; }
+impl<'a> Tr for S { type Output = &'a S; }
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let trait_ = db.first_trait(file_id);
+    let adt = db.first_adt(file_id);
+    let s = Ty::Adt(adt, Substs::empty());
+    let projection_ty = ProjectionTy {
+        associated_ty: db.trait_data(trait_).associated_type_by_name(&name![Output]).unwrap(),
+        parameters: Substs::single(s.clone()),
+    };
+    let ty = Ty::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let pred = ProjectionPredicate { projection_ty, ty };
+    let env = Arc::new(TraitEnvironment { predicates: Vec::new() });
+    let goal = Canonical {
+        value: InEnvironment::new(env, Obligation::Projection(pred)),
+        kinds: Arc::new([VariableKind::Ty(TyVariableKind::General)]),
+    };
+
+    let solution = match db.trait_solve(module.krate(), goal) {
+        Some(Solution::Unique(it)) => it.0,
+        solution => panic!("unexpected solution {:?}", solution),
+    };
+    // `Ty::Ref` has no lifetime, and we pass `'static` to Chalk in its place,
+    // so there's no variable for `'a`
+    assert_eq!(solution.kinds.len(), 0);
+    assert_eq!(solution.value[..], [Ty::Ref(Mutability::Not, Substs::single(s))]);
+}
+
+#[test]
+fn with_predicate_makes_goal_provable() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Foo {}
+fn f<U>() {}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let trait_ = db.first_trait(file_id);
+    let func = db.first_fn(file_id);
+    let u = Substs::type_params(&db, func)[0].clone();
+    let trait_ref = TraitRef { trait_, substs: Substs::single(u) };
+
+    let goal = in_env_for_def(&db, func.into(), Obligation::Trait(trait_ref.clone()));
+    let extended = goal.environment.with_predicate(GenericPredicate::Implemented(trait_ref));
+    assert!(extended.predicates.starts_with(&goal.environment.predicates));
+    let extended_goal = InEnvironment::new(extended, goal.value.clone());
+
+    assert!(db.trait_solve(module.krate(), Canonical::new(goal, None)).is_none());
+    let solution = db.trait_solve(module.krate(), Canonical::new(extended_goal, None));
+    assert!(matches!(solution, Some(Solution::Unique(_))));
+}
+
+#[test]
+fn trait_datum_has_supertrait_and_assoc_ty_bounds() {
+    let (db, file_id) = TestDB::with_single_file(
+        r#"
+trait Super {}
+trait Sub: Super {
+    type Item: Super;
+}
+"#,
+    );
+    let module = db.module_for_file(file_id);
+    let traits = db.traits(file_id);
+    let (sub, super_) = match traits[..] {
+        [a, b] if db.trait_data(a).associated_types().next().is_some() => (a, b),
+        [a, b] => (b, a),
+        _ => panic!("expected two traits"),
+    };
+    let self_ty = Ty::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    let implements_super =
+        GenericPredicate::Implemented(TraitRef { trait_: super_, substs: Substs::single(self_ty) });
+
+    let datum = db.trait_datum(module.krate(), sub.to_chalk(&db));
+    let where_clauses = &datum.binders.skip_binders().where_clauses;
+    let preds = where_clauses
+        .iter()
+        .map(|it| chalk::from_chalk::<GenericPredicate, _>(&db, Some(it.clone())))
+        .collect::<Vec<_>>();
+    assert!(preds.contains(&implements_super), "{:?}", preds);
+    assert_eq!(datum.associated_ty_ids.len(), 1);
+
+    let assoc_ty = db.associated_ty_data(datum.associated_ty_ids[0]);
+    let bounds = &assoc_ty.binders.skip_binders().bounds;
+    match bounds.iter().map(|it| it.skip_binders()).collect::<Vec<_>>()[..] {
+        [chalk_solve::rust_ir::InlineBound::TraitBound(bound)] => {
+            assert_eq!(bound.trait_id, super_.to_chalk(&db))
+        }
+        ref bounds => panic!("expected a `Super` bound, got {:?}", bounds),
+    }
+}
+
+#[test]
+fn callable_sig_chalk_converts_params_and_return_type() {
+    let (db, file_id) = TestDB::with_single_file("fn foo<T>(a: T, b: u32) -> bool {}");
+    let func = db.first_fn(file_id);
+
+    let sig = db.callable_sig_chalk(CallableDefId::FunctionId(func));
+    assert_eq!(sig.num_binders, 1);
+    assert_eq!(sig.value.argument_types.len(), 2);
+    let t = Ty::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    assert_eq!(sig.value.argument_types[0], t.to_chalk(&db));
+    assert_eq!(sig.value.return_type, Ty::Scalar(Scalar::Bool).to_chalk(&db));
+}
+
+#[test]
+fn unresolved_where_clause_is_recorded_as_skipped() {
+    let (db, file_id) = TestDB::with_single_file("fn f<T: Missing>() {}");
+    let func = db.first_fn(file_id);
+
+    let skipped = skipped_where_clauses(&db, func.into());
+    assert_eq!(
+        skipped,
+        [SkippedPredicate {
+            predicate: GenericPredicate::Error,
+            reason: SkipReason::ErrorInWhereClause
+        }]
+    );
+}
+
+#[test]
+fn solution_binding_a_const_var_keeps_it() {
+    let db = TestDB::default();
+    let usize_ = Ty::Scalar(Scalar::Uint(crate::primitive::UintTy::Usize));
+    let binders = chalk_ir::CanonicalVarKinds::from_iter(
+        &Interner,
+        Some(chalk_ir::CanonicalVarKind::new(
+            chalk_ir::VariableKind::Const(usize_.clone().to_chalk(&db)),
+            chalk_ir::UniverseIndex::ROOT,
+        )),
+    );
+    let subst = chalk_ir::ConstrainedSubst {
+        subst: Substs::empty().to_chalk(&db),
+        constraints: chalk_ir::Constraints::empty(&Interner),
+    };
+    let solution = chalk_solve::Solution::Unique(chalk_ir::Canonical { value: subst, binders });
+    assert_eq!(
+        solution_from_chalk(&db, solution),
+        Solution::Unique(SolutionVariables(Canonical::new(
+            Substs::empty(),
+            Some(VariableKind::Const(usize_))
+        )))
+    );
+}
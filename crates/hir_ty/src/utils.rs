@@ -128,6 +128,15 @@ pub(super) fn associated_type_by_name_including_super_traits(
     })
 }
 
+/// Returns the trait an associated type belongs to, or `None` if it's not
+/// declared in a trait (e.g. an inherent associated type).
+pub(crate) fn assoc_ty_trait(db: &dyn DefDatabase, type_alias: TypeAliasId) -> Option<TraitId> {
+    match type_alias.lookup(db).container {
+        AssocContainerId::TraitId(it) => Some(it),
+        AssocContainerId::ImplId(_) | AssocContainerId::ContainerId(_) => None,
+    }
+}
+
 pub(super) fn variant_data(db: &dyn DefDatabase, var: VariantId) -> Arc<VariantData> {
     match var {
         VariantId::StructId(it) => db.struct_data(it).variant_data.clone(),
@@ -0,0 +1,189 @@
+//! Computes the variance of ADTs in their type parameters, from the types of
+//! their fields.
+use std::sync::Arc;
+
+use hir_def::{AdtId, EnumVariantId, VariantId};
+
+use crate::{db::HirDatabase, utils::generics, DebruijnIndex, Mutability, Ty, TypeWalk, Variance};
+
+pub(crate) fn adt_variances_query(db: &dyn HirDatabase, adt: AdtId) -> Arc<[Variance]> {
+    let num_params = generics(db.upcast(), adt.into()).len();
+    let field_tys = variants(db, adt)
+        .into_iter()
+        .flat_map(|variant| {
+            db.field_types(variant).iter().map(|(_, ty)| ty.value.clone()).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // `None` means the parameter hasn't been used so far, i.e. it's bivariant.
+    // Uses of `adt` in its own fields are resolved by iterating to a fixpoint.
+    let mut variances = vec![None; num_params];
+    loop {
+        let mut ctx = Ctx { db, adt, current: &variances, result: vec![None; num_params] };
+        for ty in &field_tys {
+            ctx.add(ty, Variance::Covariant);
+        }
+        if ctx.result == variances {
+            break;
+        }
+        variances = ctx.result;
+    }
+    // An unused parameter is an error unless it's `PhantomData`'s, which is
+    // covariant.
+    variances.into_iter().map(|it| it.unwrap_or(Variance::Covariant)).collect()
+}
+
+pub(crate) fn adt_variances_recover(
+    db: &dyn HirDatabase,
+    _cycle: &[String],
+    adt: &AdtId,
+) -> Arc<[Variance]> {
+    let num_params = generics(db.upcast(), (*adt).into()).len();
+    vec![Variance::Invariant; num_params].into()
+}
+
+fn variants(db: &dyn HirDatabase, adt: AdtId) -> Vec<VariantId> {
+    match adt {
+        AdtId::StructId(it) => vec![it.into()],
+        AdtId::UnionId(it) => vec![it.into()],
+        AdtId::EnumId(parent) => db
+            .enum_data(parent)
+            .variants
+            .iter()
+            .map(|(local_id, _)| EnumVariantId { parent, local_id }.into())
+            .collect(),
+    }
+}
+
+struct Ctx<'a> {
+    db: &'a dyn HirDatabase,
+    adt: AdtId,
+    current: &'a [Option<Variance>],
+    result: Vec<Option<Variance>>,
+}
+
+impl Ctx<'_> {
+    /// Records the uses of the ADT's parameters in `ty`, which is in a
+    /// `variance` position.
+    fn add(&mut self, ty: &Ty, variance: Variance) {
+        match ty {
+            Ty::BoundVar(bound) if bound.debruijn == DebruijnIndex::INNERMOST => {
+                self.use_param(bound.index, variance)
+            }
+            Ty::Ref(mutability, substs) | Ty::Raw(mutability, substs) => {
+                let variance = match mutability {
+                    Mutability::Not => variance,
+                    Mutability::Mut => Variance::Invariant,
+                };
+                self.add(&substs[0], variance);
+            }
+            Ty::Array(substs) | Ty::Slice(substs) | Ty::Tuple(_, substs) => {
+                for ty in substs.iter() {
+                    self.add(ty, variance);
+                }
+            }
+            Ty::Function(fn_ptr) => {
+                let (params, ret) = fn_ptr.substs.split_at(fn_ptr.num_args);
+                for ty in params {
+                    self.add(ty, xform(variance, Variance::Contravariant));
+                }
+                for ty in ret {
+                    self.add(ty, variance);
+                }
+            }
+            Ty::Adt(adt, substs) => {
+                let variances = if *adt == self.adt {
+                    self.current.to_vec()
+                } else {
+                    self.db.adt_variances(*adt).iter().copied().map(Some).collect()
+                };
+                for (ty, param_variance) in substs.iter().zip(variances) {
+                    if let Some(param_variance) = param_variance {
+                        self.add(ty, xform(variance, param_variance));
+                    }
+                }
+            }
+            _ => {
+                // anything else (trait objects, projections, ...) makes the
+                // parameters it mentions invariant
+                ty.clone().walk_mut_binders(
+                    &mut |ty, binders| match ty {
+                        Ty::BoundVar(bound) if bound.debruijn == binders => {
+                            self.use_param(bound.index, Variance::Invariant)
+                        }
+                        _ => (),
+                    },
+                    DebruijnIndex::INNERMOST,
+                );
+            }
+        }
+    }
+
+    fn use_param(&mut self, idx: usize, variance: Variance) {
+        let slot = &mut self.result[idx];
+        *slot = match *slot {
+            None => Some(variance),
+            Some(it) if it == variance => Some(it),
+            Some(_) => Some(Variance::Invariant),
+        };
+    }
+}
+
+/// The variance of a position with `inner` variance nested in one with
+/// `outer` variance.
+fn xform(outer: Variance, inner: Variance) -> Variance {
+    match (outer, inner) {
+        (Variance::Invariant, _) | (_, Variance::Invariant) => Variance::Invariant,
+        (Variance::Covariant, it) => it,
+        (Variance::Contravariant, Variance::Covariant) => Variance::Contravariant,
+        (Variance::Contravariant, Variance::Contravariant) => Variance::Covariant,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base_db::fixture::WithFixture;
+    use hir_def::db::DefDatabase;
+
+    use crate::test_db::TestDB;
+
+    use super::*;
+
+    #[test]
+    fn variances_from_field_types() {
+        let (db, file_id) = TestDB::with_single_file(
+            r#"
+struct Ref<'a, T> { r: &'a T }
+struct Mut<'a, T> { r: &'a mut T }
+struct Callback<T, U> { f: fn(T) -> U }
+struct Both<T> { r: Ref<'static, T>, f: Callback<T, ()> }
+enum List<T> { Nil, Cons(T, *const List<T>) }
+"#,
+        );
+        let mut variances = db
+            .adts(file_id)
+            .into_iter()
+            .map(|adt| {
+                let name = match adt {
+                    AdtId::StructId(it) => db.struct_data(it).name.clone(),
+                    AdtId::UnionId(it) => db.union_data(it).name.clone(),
+                    AdtId::EnumId(it) => db.enum_data(it).name.clone(),
+                };
+                (name.to_string(), db.adt_variances(adt).to_vec())
+            })
+            .collect::<Vec<_>>();
+        variances.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+
+        use Variance::*;
+        assert_eq!(
+            variances,
+            vec![
+                ("Both".to_string(), vec![Invariant]),
+                ("Callback".to_string(), vec![Contravariant, Covariant]),
+                ("List".to_string(), vec![Covariant]),
+                ("Mut".to_string(), vec![Invariant]),
+                ("Ref".to_string(), vec![Covariant]),
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use super::check_doc_test;
 
 #[test]
 fn doctest_add_explicit_type() {
+    // location: crates/ide_assists/src/handlers/add_explicit_type.rs
     check_doc_test(
         "add_explicit_type",
         r#####"
@@ -21,6 +22,7 @@ fn main() {
 
 #[test]
 fn doctest_add_hash() {
+    // location: crates/ide_assists/src/handlers/raw_string.rs
     check_doc_test(
         "add_hash",
         r#####"
@@ -38,6 +40,7 @@ fn main() {
 
 #[test]
 fn doctest_add_impl_default_members() {
+    // location: crates/ide_assists/src/handlers/add_missing_impl_members.rs
     check_doc_test(
         "add_impl_default_members",
         r#####"
@@ -72,6 +75,7 @@ impl Trait for () {
 
 #[test]
 fn doctest_add_impl_missing_members() {
+    // location: crates/ide_assists/src/handlers/add_missing_impl_members.rs
     check_doc_test(
         "add_impl_missing_members",
         r#####"
@@ -105,6 +109,7 @@ impl Trait<u32> for () {
 
 #[test]
 fn doctest_add_lifetime_to_type() {
+    // location: crates/ide_assists/src/handlers/add_lifetime_to_type.rs
     check_doc_test(
         "add_lifetime_to_type",
         r#####"
@@ -124,6 +129,7 @@ struct Point<'a> {
 
 #[test]
 fn doctest_add_turbo_fish() {
+    // location: crates/ide_assists/src/handlers/add_turbo_fish.rs
     check_doc_test(
         "add_turbo_fish",
         r#####"
@@ -143,6 +149,7 @@ fn main() {
 
 #[test]
 fn doctest_apply_demorgan() {
+    // location: crates/ide_assists/src/handlers/apply_demorgan.rs
     check_doc_test(
         "apply_demorgan",
         r#####"
@@ -160,6 +167,7 @@ fn main() {
 
 #[test]
 fn doctest_auto_import() {
+    // location: crates/ide_assists/src/handlers/auto_import.rs
     check_doc_test(
         "auto_import",
         r#####"
@@ -181,6 +189,7 @@ pub mod std { pub mod collections { pub struct HashMap { } } }
 
 #[test]
 fn doctest_change_visibility() {
+    // location: crates/ide_assists/src/handlers/change_visibility.rs
     check_doc_test(
         "change_visibility",
         r#####"
@@ -194,6 +203,7 @@ pub(crate) fn frobnicate() {}
 
 #[test]
 fn doctest_convert_integer_literal() {
+    // location: crates/ide_assists/src/handlers/convert_integer_literal.rs
     check_doc_test(
         "convert_integer_literal",
         r#####"
@@ -207,6 +217,7 @@ const _: i32 = 0b1010;
 
 #[test]
 fn doctest_convert_to_guarded_return() {
+    // location: crates/ide_assists/src/handlers/early_return.rs
     check_doc_test(
         "convert_to_guarded_return",
         r#####"
@@ -231,6 +242,7 @@ fn main() {
 
 #[test]
 fn doctest_expand_glob_import() {
+    // location: crates/ide_assists/src/handlers/expand_glob_import.rs
     check_doc_test(
         "expand_glob_import",
         r#####"
@@ -258,6 +270,7 @@ fn qux(bar: Bar, baz: Baz) {}
 
 #[test]
 fn doctest_extract_function() {
+    // location: crates/ide_assists/src/handlers/extract_function.rs
     check_doc_test(
         "extract_function",
         r#####"
@@ -285,6 +298,7 @@ fn $0fun_name(n: i32) {
 
 #[test]
 fn doctest_extract_struct_from_enum_variant() {
+    // location: crates/ide_assists/src/handlers/extract_struct_from_enum_variant.rs
     check_doc_test(
         "extract_struct_from_enum_variant",
         r#####"
@@ -300,6 +314,7 @@ enum A { One(One) }
 
 #[test]
 fn doctest_extract_variable() {
+    // location: crates/ide_assists/src/handlers/extract_variable.rs
     check_doc_test(
         "extract_variable",
         r#####"
@@ -318,6 +333,7 @@ fn main() {
 
 #[test]
 fn doctest_fill_match_arms() {
+    // location: crates/ide_assists/src/handlers/fill_match_arms.rs
     check_doc_test(
         "fill_match_arms",
         r#####"
@@ -344,6 +360,7 @@ fn handle(action: Action) {
 
 #[test]
 fn doctest_fix_visibility() {
+    // location: crates/ide_assists/src/handlers/fix_visibility.rs
     check_doc_test(
         "fix_visibility",
         r#####"
@@ -367,6 +384,7 @@ fn main() {
 
 #[test]
 fn doctest_flip_binexpr() {
+    // location: crates/ide_assists/src/handlers/flip_binexpr.rs
     check_doc_test(
         "flip_binexpr",
         r#####"
@@ -384,6 +402,7 @@ fn main() {
 
 #[test]
 fn doctest_flip_comma() {
+    // location: crates/ide_assists/src/handlers/flip_comma.rs
     check_doc_test(
         "flip_comma",
         r#####"
@@ -401,6 +420,7 @@ fn main() {
 
 #[test]
 fn doctest_flip_trait_bound() {
+    // location: crates/ide_assists/src/handlers/flip_trait_bound.rs
     check_doc_test(
         "flip_trait_bound",
         r#####"
@@ -414,6 +434,7 @@ fn foo<T: Copy + Clone>() { }
 
 #[test]
 fn doctest_generate_default_from_enum_variant() {
+    // location: crates/ide_assists/src/handlers/generate_default_from_enum_variant.rs
     check_doc_test(
         "generate_default_from_enum_variant",
         r#####"
@@ -441,6 +462,7 @@ impl Default for Version {
 
 #[test]
 fn doctest_generate_derive() {
+    // location: crates/ide_assists/src/handlers/generate_derive.rs
     check_doc_test(
         "generate_derive",
         r#####"
@@ -461,6 +483,7 @@ struct Point {
 
 #[test]
 fn doctest_generate_enum_as_method() {
+    // location: crates/ide_assists/src/handlers/generate_enum_projection_method.rs
     check_doc_test(
         "generate_enum_as_method",
         r#####"
@@ -490,6 +513,7 @@ impl Value {
 
 #[test]
 fn doctest_generate_enum_is_method() {
+    // location: crates/ide_assists/src/handlers/generate_enum_is_method.rs
     check_doc_test(
         "generate_enum_is_method",
         r#####"
@@ -518,6 +542,7 @@ impl Version {
 
 #[test]
 fn doctest_generate_enum_try_into_method() {
+    // location: crates/ide_assists/src/handlers/generate_enum_projection_method.rs
     check_doc_test(
         "generate_enum_try_into_method",
         r#####"
@@ -547,6 +572,7 @@ impl Value {
 
 #[test]
 fn doctest_generate_from_impl_for_enum() {
+    // location: crates/ide_assists/src/handlers/generate_from_impl_for_enum.rs
     check_doc_test(
         "generate_from_impl_for_enum",
         r#####"
@@ -566,6 +592,7 @@ impl From<u32> for A {
 
 #[test]
 fn doctest_generate_function() {
+    // location: crates/ide_assists/src/handlers/generate_function.rs
     check_doc_test(
         "generate_function",
         r#####"
@@ -593,6 +620,7 @@ fn bar(arg: &str, baz: Baz) ${0:-> ()} {
 
 #[test]
 fn doctest_generate_getter() {
+    // location: crates/ide_assists/src/handlers/generate_getter.rs
     check_doc_test(
         "generate_getter",
         r#####"
@@ -617,6 +645,7 @@ impl Person {
 
 #[test]
 fn doctest_generate_getter_mut() {
+    // location: crates/ide_assists/src/handlers/generate_getter_mut.rs
     check_doc_test(
         "generate_getter_mut",
         r#####"
@@ -641,6 +670,7 @@ impl Person {
 
 #[test]
 fn doctest_generate_impl() {
+    // location: crates/ide_assists/src/handlers/generate_impl.rs
     check_doc_test(
         "generate_impl",
         r#####"
@@ -662,6 +692,7 @@ impl<T: Clone> Ctx<T> {
 
 #[test]
 fn doctest_generate_new() {
+    // location: crates/ide_assists/src/handlers/generate_new.rs
     check_doc_test(
         "generate_new",
         r#####"
@@ -683,6 +714,7 @@ impl<T: Clone> Ctx<T> {
 
 #[test]
 fn doctest_generate_setter() {
+    // location: crates/ide_assists/src/handlers/generate_setter.rs
     check_doc_test(
         "generate_setter",
         r#####"
@@ -707,6 +739,7 @@ impl Person {
 
 #[test]
 fn doctest_infer_function_return_type() {
+    // location: crates/ide_assists/src/handlers/infer_function_return_type.rs
     check_doc_test(
         "infer_function_return_type",
         r#####"
@@ -720,6 +753,7 @@ fn foo() -> i32 { 42i32 }
 
 #[test]
 fn doctest_inline_function() {
+    // location: crates/ide_assists/src/handlers/inline_function.rs
     check_doc_test(
         "inline_function",
         r#####"
@@ -743,6 +777,7 @@ fn main() {
 
 #[test]
 fn doctest_inline_local_variable() {
+    // location: crates/ide_assists/src/handlers/inline_local_variable.rs
     check_doc_test(
         "inline_local_variable",
         r#####"
@@ -761,6 +796,7 @@ fn main() {
 
 #[test]
 fn doctest_introduce_named_lifetime() {
+    // location: crates/ide_assists/src/handlers/introduce_named_lifetime.rs
     check_doc_test(
         "introduce_named_lifetime",
         r#####"
@@ -786,6 +822,7 @@ impl<'a> Cursor<'a> {
 
 #[test]
 fn doctest_invert_if() {
+    // location: crates/ide_assists/src/handlers/invert_if.rs
     check_doc_test(
         "invert_if",
         r#####"
@@ -803,6 +840,7 @@ fn main() {
 
 #[test]
 fn doctest_make_raw_string() {
+    // location: crates/ide_assists/src/handlers/raw_string.rs
     check_doc_test(
         "make_raw_string",
         r#####"
@@ -820,6 +858,7 @@ fn main() {
 
 #[test]
 fn doctest_make_usual_string() {
+    // location: crates/ide_assists/src/handlers/raw_string.rs
     check_doc_test(
         "make_usual_string",
         r#####"
@@ -837,6 +876,7 @@ fn main() {
 
 #[test]
 fn doctest_merge_imports() {
+    // location: crates/ide_assists/src/handlers/merge_imports.rs
     check_doc_test(
         "merge_imports",
         r#####"
@@ -851,6 +891,7 @@ use std::{fmt::Formatter, io};
 
 #[test]
 fn doctest_merge_match_arms() {
+    // location: crates/ide_assists/src/handlers/merge_match_arms.rs
     check_doc_test(
         "merge_match_arms",
         r#####"
@@ -877,6 +918,7 @@ fn handle(action: Action) {
 
 #[test]
 fn doctest_move_arm_cond_to_match_guard() {
+    // location: crates/ide_assists/src/handlers/move_guard.rs
     check_doc_test(
         "move_arm_cond_to_match_guard",
         r#####"
@@ -904,6 +946,7 @@ fn handle(action: Action) {
 
 #[test]
 fn doctest_move_bounds_to_where_clause() {
+    // location: crates/ide_assists/src/handlers/move_bounds.rs
     check_doc_test(
         "move_bounds_to_where_clause",
         r#####"
@@ -921,6 +964,7 @@ fn apply<T, U, F>(f: F, x: T) -> U where F: FnOnce(T) -> U {
 
 #[test]
 fn doctest_move_guard_to_arm_body() {
+    // location: crates/ide_assists/src/handlers/move_guard.rs
     check_doc_test(
         "move_guard_to_arm_body",
         r#####"
@@ -950,6 +994,7 @@ fn handle(action: Action) {
 
 #[test]
 fn doctest_move_module_to_file() {
+    // location: crates/ide_assists/src/handlers/move_module_to_file.rs
     check_doc_test(
         "move_module_to_file",
         r#####"
@@ -965,6 +1010,7 @@ mod foo;
 
 #[test]
 fn doctest_pull_assignment_up() {
+    // location: crates/ide_assists/src/handlers/pull_assignment_up.rs
     check_doc_test(
         "pull_assignment_up",
         r#####"
@@ -994,6 +1040,7 @@ fn main() {
 
 #[test]
 fn doctest_qualify_path() {
+    // location: crates/ide_assists/src/handlers/qualify_path.rs
     check_doc_test(
         "qualify_path",
         r#####"
@@ -1013,6 +1060,7 @@ pub mod std { pub mod collections { pub struct HashMap { } } }
 
 #[test]
 fn doctest_remove_dbg() {
+    // location: crates/ide_assists/src/handlers/remove_dbg.rs
     check_doc_test(
         "remove_dbg",
         r#####"
@@ -1030,6 +1078,7 @@ fn main() {
 
 #[test]
 fn doctest_remove_hash() {
+    // location: crates/ide_assists/src/handlers/raw_string.rs
     check_doc_test(
         "remove_hash",
         r#####"
@@ -1047,6 +1096,7 @@ fn main() {
 
 #[test]
 fn doctest_remove_mut() {
+    // location: crates/ide_assists/src/handlers/remove_mut.rs
     check_doc_test(
         "remove_mut",
         r#####"
@@ -1064,6 +1114,7 @@ impl Walrus {
 
 #[test]
 fn doctest_remove_unused_param() {
+    // location: crates/ide_assists/src/handlers/remove_unused_param.rs
     check_doc_test(
         "remove_unused_param",
         r#####"
@@ -1085,6 +1136,7 @@ fn main() {
 
 #[test]
 fn doctest_reorder_fields() {
+    // location: crates/ide_assists/src/handlers/reorder_fields.rs
     check_doc_test(
         "reorder_fields",
         r#####"
@@ -1100,6 +1152,7 @@ const test: Foo = Foo {foo: 1, bar: 0}
 
 #[test]
 fn doctest_reorder_impl() {
+    // location: crates/ide_assists/src/handlers/reorder_impl.rs
     check_doc_test(
         "reorder_impl",
         r#####"
@@ -1135,6 +1188,7 @@ impl Foo for Bar {
 
 #[test]
 fn doctest_replace_derive_with_manual_impl() {
+    // location: crates/ide_assists/src/handlers/replace_derive_with_manual_impl.rs
     check_doc_test(
         "replace_derive_with_manual_impl",
         r#####"
@@ -1158,6 +1212,7 @@ impl Debug for S {
 
 #[test]
 fn doctest_replace_for_loop_with_for_each() {
+    // location: crates/ide_assists/src/handlers/replace_for_loop_with_for_each.rs
     check_doc_test(
         "replace_for_loop_with_for_each",
         r#####"
@@ -1181,6 +1236,7 @@ fn main() {
 
 #[test]
 fn doctest_replace_if_let_with_match() {
+    // location: crates/ide_assists/src/handlers/replace_if_let_with_match.rs
     check_doc_test(
         "replace_if_let_with_match",
         r#####"
@@ -1209,6 +1265,7 @@ fn handle(action: Action) {
 
 #[test]
 fn doctest_replace_impl_trait_with_generic() {
+    // location: crates/ide_assists/src/handlers/replace_impl_trait_with_generic.rs
     check_doc_test(
         "replace_impl_trait_with_generic",
         r#####"
@@ -1222,6 +1279,7 @@ fn foo<B: Bar>(bar: B) {}
 
 #[test]
 fn doctest_replace_let_with_if_let() {
+    // location: crates/ide_assists/src/handlers/replace_let_with_if_let.rs
     check_doc_test(
         "replace_let_with_if_let",
         r#####"
@@ -1248,6 +1306,7 @@ fn compute() -> Option<i32> { None }
 
 #[test]
 fn doctest_replace_match_with_if_let() {
+    // location: crates/ide_assists/src/handlers/replace_if_let_with_match.rs
     check_doc_test(
         "replace_match_with_if_let",
         r#####"
@@ -1276,6 +1335,7 @@ fn handle(action: Action) {
 
 #[test]
 fn doctest_replace_qualified_name_with_use() {
+    // location: crates/ide_assists/src/handlers/replace_qualified_name_with_use.rs
     check_doc_test(
         "replace_qualified_name_with_use",
         r#####"
@@ -1291,6 +1351,7 @@ fn process(map: HashMap<String, String>) {}
 
 #[test]
 fn doctest_replace_string_with_char() {
+    // location: crates/ide_assists/src/handlers/replace_string_with_char.rs
     check_doc_test(
         "replace_string_with_char",
         r#####"
@@ -1308,6 +1369,7 @@ fn main() {
 
 #[test]
 fn doctest_replace_unwrap_with_match() {
+    // location: crates/ide_assists/src/handlers/replace_unwrap_with_match.rs
     check_doc_test(
         "replace_unwrap_with_match",
         r#####"
@@ -1332,6 +1394,7 @@ fn main() {
 
 #[test]
 fn doctest_split_import() {
+    // location: crates/ide_assists/src/handlers/split_import.rs
     check_doc_test(
         "split_import",
         r#####"
@@ -1345,6 +1408,7 @@ use std::{collections::HashMap};
 
 #[test]
 fn doctest_toggle_ignore() {
+    // location: crates/ide_assists/src/handlers/toggle_ignore.rs
     check_doc_test(
         "toggle_ignore",
         r#####"
@@ -1365,6 +1429,7 @@ fn arithmetics {
 
 #[test]
 fn doctest_unmerge_use() {
+    // location: crates/ide_assists/src/handlers/unmerge_use.rs
     check_doc_test(
         "unmerge_use",
         r#####"
@@ -1379,6 +1444,7 @@ use std::fmt::Display;
 
 #[test]
 fn doctest_unwrap_block() {
+    // location: crates/ide_assists/src/handlers/unwrap_block.rs
     check_doc_test(
         "unwrap_block",
         r#####"
@@ -1398,6 +1464,7 @@ fn foo() {
 
 #[test]
 fn doctest_wrap_return_type_in_result() {
+    // location: crates/ide_assists/src/handlers/wrap_return_type_in_result.rs
     check_doc_test(
         "wrap_return_type_in_result",
         r#####"
@@ -186,9 +186,7 @@ fn complete_enum_variants(
     ty: &hir::Type,
     cb: impl Fn(&mut Completions, &CompletionContext, hir::Variant, hir::ModPath),
 ) {
-    if let Some(hir::Adt::Enum(enum_data)) =
-        iter::successors(Some(ty.clone()), |ty| ty.remove_ref()).last().and_then(|ty| ty.as_adt())
-    {
+    if let Some(hir::Adt::Enum(enum_data)) = ty.strip_references().as_adt() {
         let variants = enum_data.variants(ctx.db);
 
         let module = if let Some(module) = ctx.scope.module() {
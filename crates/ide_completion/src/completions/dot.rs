@@ -456,4 +456,29 @@ mod foo {
             "#]],
         );
     }
+
+    #[test]
+    fn completes_through_deref_of_partially_unknown_type() {
+        check(
+            r#"
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+struct S;
+impl S { fn foo(&self) {} }
+struct Wrapper<T, U>;
+impl<T, U> Deref for Wrapper<T, U> {
+    type Target = T;
+    fn deref(&self) -> &T { loop {} }
+}
+fn f(w: Wrapper<S, Unresolved>) { w.$0 }
+"#,
+            expect![[r#"
+                me deref() -> &<Self as Deref>::Target
+                me foo()   -> ()
+            "#]],
+        );
+    }
 }
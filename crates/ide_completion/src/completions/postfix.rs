@@ -35,8 +35,7 @@ pub(crate) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
         None => return,
     };
 
-    let ref_removed_ty =
-        std::iter::successors(Some(receiver_ty.clone()), |ty| ty.remove_ref()).last().unwrap();
+    let ref_removed_ty = receiver_ty.strip_references();
 
     let cap = match ctx.config.snippet_cap {
         Some(it) => it,
@@ -179,6 +179,7 @@ impl RootDatabase {
             hir::db::CallableItemSignatureQuery
             hir::db::GenericPredicatesForParamQuery
             hir::db::GenericPredicatesQuery
+            hir::db::AdtVariancesQuery
             hir::db::GenericDefaultsQuery
             hir::db::InherentImplsInCrateQuery
             hir::db::TraitImplsInCrateQuery
@@ -188,8 +189,11 @@ impl RootDatabase {
             hir::db::TraitDatumQuery
             hir::db::StructDatumQuery
             hir::db::ImplDatumQuery
+            hir::db::ImplTraitRefChalkQuery
+            hir::db::CallableSigChalkQuery
             hir::db::FnDefDatumQuery
             hir::db::ReturnTypeImplTraitsQuery
+            hir::db::TypeAliasImplTraitsQuery
             hir::db::InternCallableDefQuery
             hir::db::InternTypeParamIdQuery
             hir::db::InternImplTraitIdQuery
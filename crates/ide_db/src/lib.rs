@@ -15,14 +15,17 @@ pub mod traits;
 pub mod call_info;
 pub mod helpers;
 
-use std::{fmt, sync::Arc};
+use std::{env, fmt, sync::Arc};
 
 use base_db::{
     salsa::{self, Durability},
     AnchoredPath, Canceled, CheckCanceled, CrateId, FileId, FileLoader, FileLoaderDelegate,
     SourceDatabase, Upcast,
 };
-use hir::db::{AstDatabase, DefDatabase, HirDatabase};
+use hir::{
+    db::{AstDatabase, DefDatabase, HirDatabase},
+    SolverConfig,
+};
 use rustc_hash::FxHashSet;
 
 use crate::{line_index::LineIndex, symbol_index::SymbolsDatabase};
@@ -107,6 +110,7 @@ impl RootDatabase {
         db.set_crate_graph_with_durability(Default::default(), Durability::HIGH);
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
         db.set_library_roots_with_durability(Default::default(), Durability::HIGH);
+        db.set_solver_config_with_durability(solver_config_from_env(), Durability::HIGH);
         db.update_lru_capacity(lru_capacity);
         db
     }
@@ -119,6 +123,17 @@ impl RootDatabase {
     }
 }
 
+/// The default solver limits, overridden by the `CHALK_OVERFLOW_DEPTH` and
+/// `CHALK_SOLVER_MAX_SIZE` environment variables.
+fn solver_config_from_env() -> SolverConfig {
+    let var = |name| env::var(name).ok().and_then(|it| it.parse().ok());
+    let default = SolverConfig::default();
+    SolverConfig {
+        overflow_depth: var("CHALK_OVERFLOW_DEPTH").unwrap_or(default.overflow_depth),
+        max_size: var("CHALK_SOLVER_MAX_SIZE").unwrap_or(default.max_size),
+    }
+}
+
 impl salsa::ParallelDatabase for RootDatabase {
     fn snapshot(&self) -> salsa::Snapshot<RootDatabase> {
         salsa::Snapshot::new(RootDatabase { storage: self.storage.snapshot() })
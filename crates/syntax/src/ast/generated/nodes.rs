@@ -1,4 +1,5 @@
 //! Generated file, do not edit by hand, see `xtask/src/codegen`
+// grammar-hash: 7c0c51a6245c2094
 
 use crate::{
     ast::{self, support, AstChildren, AstNode},
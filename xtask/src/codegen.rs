@@ -11,60 +11,349 @@ mod gen_assists_docs;
 mod gen_feature_docs;
 mod gen_lint_completions;
 mod gen_diagnostic_docs;
+mod gen_to_chalk_audit;
+mod gen_chalk_fixtures;
+mod gen_obligation_docs;
+mod gen_ty_kind_tests;
 
+use anyhow::Context;
 use std::{
-    fmt, mem,
+    fmt,
     path::{Path, PathBuf},
 };
-use xshell::{cmd, pushenv, read_file, write_file};
+use xshell::{cmd, mkdir_p, pushd, pushenv, read_file, write_file};
 
-use crate::{ensure_rustfmt, flags, project_root, Result};
+use crate::{comment_blocks::CommentBlock, ensure_rustfmt, flags, project_root, Result};
 
 pub(crate) use self::{
-    gen_assists_docs::{generate_assists_docs, generate_assists_tests},
+    gen_assists_docs::{generate_assist_tests, generate_assists_docs, generate_assists_tests},
+    gen_chalk_fixtures::generate_chalk_fixtures,
     gen_diagnostic_docs::generate_diagnostic_docs,
     gen_feature_docs::generate_feature_docs,
     gen_lint_completions::generate_lint_completions,
+    gen_obligation_docs::generate_obligation_docs,
     gen_parser_tests::generate_parser_tests,
     gen_syntax::generate_syntax,
+    gen_to_chalk_audit::generate_to_chalk_audit,
+    gen_ty_kind_tests::generate_ty_kind_tests,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub(crate) enum Mode {
     Overwrite,
+    /// Fail on the first file which is not up-to-date.
     Verify,
+    /// Don't touch anything, just collect the files which are not up-to-date.
+    Check,
 }
 
+type Generator = fn(Mode) -> Result<Outcomes>;
+
+/// A generator, along with the paths its output depends on: the files it
+/// reads, its own source, and the files it generates. Directories cover all
+/// files inside of them.
+#[derive(Clone, Copy)]
+struct Target {
+    name: &'static str,
+    inputs: &'static [&'static str],
+    generate: Generator,
+}
+
+/// Inputs of every generator.
+const COMMON_INPUTS: &[&str] = &["xtask/src/codegen.rs", "rustfmt.toml"];
+
 impl flags::Codegen {
     pub(crate) fn run(self) -> Result<()> {
-        if self.features {
-            generate_lint_completions(Mode::Overwrite)?;
-        }
-        generate_syntax(Mode::Overwrite)?;
-        generate_parser_tests(Mode::Overwrite)?;
-        generate_assists_tests(Mode::Overwrite)?;
-        generate_assists_docs(Mode::Overwrite)?;
-        generate_feature_docs(Mode::Overwrite)?;
-        generate_diagnostic_docs(Mode::Overwrite)?;
+        let mode = if self.check { Mode::Check } else { Mode::Overwrite };
+        let outcomes = self.generate(mode)?;
+        if self.check {
+            let stale = stale_files(&outcomes);
+            if !stale.is_empty() {
+                eprintln!("the following generated files are not up-to-date:");
+                for path in stale {
+                    eprintln!("  {}", path.display());
+                }
+            }
+        }
+        eprintln!("{}", Summary::new(&outcomes));
         Ok(())
     }
+
+    /// Runs all generators, returning what happened to each generated file.
+    /// With `--since`, generators whose inputs didn't change are skipped. With
+    /// `--assist`, only that assist's tests are generated.
+    fn generate(&self, mode: Mode) -> Result<Outcomes> {
+        if let Some(id) = &self.assist {
+            return generate_assist_tests(id, mode);
+        }
+        let mut targets = self.generators();
+        if let Some(rev) = &self.since {
+            let changed = changed_since(&project_root(), rev)?;
+            targets = affected_targets(targets, &changed, |target| {
+                eprintln!("skipping {}, its inputs didn't change since {}", target.name, rev)
+            });
+        }
+        run_generators(&targets, mode, self.fail_fast.unwrap_or(true))
+    }
+
+    fn generators(&self) -> Vec<Target> {
+        let mut res = vec![
+            Target {
+                name: "lint completions",
+                inputs: &[
+                    "xtask/src/codegen/gen_lint_completions.rs",
+                    "crates/ide_completion/src/generated_lint_completions.rs",
+                ],
+                generate: generate_lint_completions,
+            },
+            Target {
+                name: "syntax",
+                inputs: &[
+                    "xtask/src/codegen/gen_syntax.rs",
+                    "xtask/src/ast_src.rs",
+                    "crates/syntax/rust.ungram",
+                    // the grammar is parsed by the `ungrammar` crate
+                    "xtask/Cargo.toml",
+                    "crates/parser/src/syntax_kind/generated.rs",
+                    "crates/syntax/src/ast/generated",
+                ],
+                generate: generate_syntax,
+            },
+            Target {
+                name: "parser tests",
+                inputs: &[
+                    "xtask/src/codegen/gen_parser_tests.rs",
+                    "crates/parser/src/grammar",
+                    "crates/syntax/test_data/parser/inline",
+                ],
+                generate: generate_parser_tests,
+            },
+            Target {
+                name: "assists tests",
+                inputs: &[
+                    "xtask/src/codegen/gen_assists_docs.rs",
+                    "crates/ide_assists/src/handlers",
+                    "crates/ide_assists/src/tests/generated.rs",
+                ],
+                generate: generate_assists_tests,
+            },
+            Target {
+                name: "assists docs",
+                inputs: &[
+                    "xtask/src/codegen/gen_assists_docs.rs",
+                    "crates/ide_assists/src/handlers",
+                    "docs/user/generated_assists.adoc",
+                ],
+                generate: generate_assists_docs,
+            },
+            Target {
+                name: "feature docs",
+                inputs: &[
+                    "xtask/src/codegen/gen_feature_docs.rs",
+                    "crates",
+                    "docs/user/generated_features.adoc",
+                ],
+                generate: generate_feature_docs,
+            },
+            Target {
+                name: "diagnostic docs",
+                inputs: &[
+                    "xtask/src/codegen/gen_diagnostic_docs.rs",
+                    "crates",
+                    "docs/user/generated_diagnostic.adoc",
+                ],
+                generate: generate_diagnostic_docs,
+            },
+            Target {
+                name: "ToChalk audit",
+                inputs: &[
+                    "xtask/src/codegen/gen_to_chalk_audit.rs",
+                    "crates/hir_ty/src/traits/chalk/mapping.rs",
+                    "crates/hir_ty/src/lib.rs",
+                    "docs/dev/generated_to_chalk_audit.md",
+                ],
+                generate: generate_to_chalk_audit,
+            },
+            Target {
+                name: "ToChalk fixtures",
+                inputs: &[
+                    "xtask/src/codegen/gen_chalk_fixtures.rs",
+                    "crates/hir_ty/src/traits/chalk/mapping/tests/fixtures.txt",
+                    "crates/hir_ty/src/traits/chalk/mapping/tests/fixtures.rs",
+                ],
+                generate: generate_chalk_fixtures,
+            },
+            Target {
+                name: "obligation docs",
+                inputs: &[
+                    "xtask/src/codegen/gen_obligation_docs.rs",
+                    "crates/hir_ty/src/traits.rs",
+                    "docs/dev/generated_obligations.md",
+                ],
+                generate: generate_obligation_docs,
+            },
+            Target {
+                name: "TyKind tests",
+                inputs: &[
+                    "xtask/src/codegen/gen_ty_kind_tests.rs",
+                    "crates/hir_ty/src/traits/chalk/mapping.rs",
+                    // the `TyKind` variants come from the locked `chalk-ir`
+                    "Cargo.lock",
+                    "crates/hir_ty/src/traits/chalk/mapping/tests/ty_kinds.rs",
+                ],
+                generate: generate_ty_kind_tests,
+            },
+        ];
+        if self.skip_lint_completions {
+            res.retain(|target| target.name != "lint completions");
+        }
+        res
+    }
+}
+
+/// The paths (relative to the repository `root`) that changed since `rev`,
+/// including uncommitted changes and new files.
+fn changed_since(root: &Path, rev: &str) -> Result<Vec<String>> {
+    let _d = pushd(root)?;
+    let changed = cmd!("git diff --name-only {rev}")
+        .read()
+        .with_context(|| format!("failed to list the files changed since `{}`", rev))?;
+    // `git diff` leaves out files that were never added
+    let untracked = cmd!("git ls-files --others --exclude-standard")
+        .read()
+        .context("failed to list the untracked files")?;
+    Ok(changed.lines().chain(untracked.lines()).map(String::from).collect())
+}
+
+/// The targets with an input among the `changed` paths, calling `skip` on the
+/// others.
+fn affected_targets(
+    targets: Vec<Target>,
+    changed: &[String],
+    mut skip: impl FnMut(&Target),
+) -> Vec<Target> {
+    let is_changed = |input: &str| {
+        changed.iter().any(|path| {
+            path == input || (path.starts_with(input) && path[input.len()..].starts_with('/'))
+        })
+    };
+    targets
+        .into_iter()
+        .filter(|target| {
+            let affected = COMMON_INPUTS.iter().chain(target.inputs).any(|it| is_changed(it));
+            if !affected {
+                skip(target);
+            }
+            affected
+        })
+        .collect()
+}
+
+/// Runs the generators of `targets`, returning what happened to each generated file. Unless
+/// `fail_fast` is set, a failing generator doesn't stop the rest, and the
+/// failures are reported together once all generators ran.
+fn run_generators(targets: &[Target], mode: Mode, fail_fast: bool) -> Result<Outcomes> {
+    let mut outcomes = Vec::new();
+    let mut failures = Vec::new();
+    for target in targets {
+        match (target.generate)(mode) {
+            Ok(it) => outcomes.extend(it),
+            Err(err) if fail_fast => return Err(err),
+            Err(err) => failures.push(format!("{}: {:#}", target.name, err)),
+        }
+    }
+    if !failures.is_empty() {
+        anyhow::bail!("{} generators failed:\n{}", failures.len(), failures.join("\n"));
+    }
+    Ok(outcomes)
+}
+
+/// What `update` did with a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Unchanged,
+    Updated,
+    /// Out of date, but left alone because of `Mode::Check`.
+    Stale,
+}
+
+/// The files a generator passed to `update`, along with what happened to them.
+pub(crate) type Outcomes = Vec<(PathBuf, Outcome)>;
+
+fn stale_files(outcomes: &[(PathBuf, Outcome)]) -> Vec<PathBuf> {
+    outcomes
+        .iter()
+        .filter(|(_, outcome)| *outcome == Outcome::Stale)
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Summary {
+    updated: usize,
+    unchanged: usize,
+    stale: usize,
+}
+
+impl Summary {
+    fn new(outcomes: &[(PathBuf, Outcome)]) -> Summary {
+        let mut res = Summary::default();
+        for (_, outcome) in outcomes {
+            match outcome {
+                Outcome::Unchanged => res.unchanged += 1,
+                Outcome::Updated => res.updated += 1,
+                Outcome::Stale => res.stale += 1,
+            }
+        }
+        res
+    }
 }
 
-/// A helper to update file on disk if it has changed.
-/// With verify = false,
-fn update(path: &Path, contents: &str, mode: Mode) -> Result<()> {
-    match read_file(path) {
-        Ok(old_contents) if normalize(&old_contents) == normalize(contents) => {
-            return Ok(());
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "codegen: {} updated, {} unchanged, {} stale",
+            self.updated, self.unchanged, self.stale
+        )
+    }
+}
+
+/// A helper to update file on disk if it has changed. If `path` isn't
+/// up-to-date, `Mode::Overwrite` writes `contents` to it, `Mode::Verify` fails
+/// and `Mode::Check` just reports it as stale.
+fn update(path: &Path, contents: &str, mode: Mode) -> Result<Outcome> {
+    let old_contents = read_file(path).ok();
+    if let Some(old_contents) = &old_contents {
+        if normalize(old_contents) == normalize(contents) {
+            return Ok(Outcome::Unchanged);
         }
-        _ => (),
     }
     if mode == Mode::Verify {
+        if old_contents.is_none() {
+            anyhow::bail!(
+                "generated file `{}` is missing, run `cargo xtask codegen`",
+                path.display()
+            );
+        }
+        if matches!(&old_contents, Some(it) if it.contains(PREAMBLE)) {
+            anyhow::bail!(
+                "`{}` is generated and out of date, run `cargo xtask codegen` \
+                 instead of editing it by hand",
+                path.display()
+            );
+        }
         anyhow::bail!("`{}` is not up-to-date", path.display());
     }
+    if mode == Mode::Check {
+        return Ok(Outcome::Stale);
+    }
     eprintln!("updating {}", path.display());
+    if let Some(parent) = path.parent() {
+        mkdir_p(parent)?;
+    }
     write_file(path, contents)?;
-    return Ok(());
+    return Ok(Outcome::Updated);
 
     fn normalize(s: &str) -> String {
         s.replace("\r\n", "\n")
@@ -73,96 +362,385 @@ fn update(path: &Path, contents: &str, mode: Mode) -> Result<()> {
 
 const PREAMBLE: &str = "Generated file, do not edit by hand, see `xtask/src/codegen`";
 
-fn reformat(text: &str) -> Result<String> {
+/// Formats generated code with rustfmt. `label` names what's being generated,
+/// so that a generator emitting invalid code can be tracked down.
+fn reformat(label: &str, text: &str) -> Result<String> {
+    reformat_with(label, text, &[])
+}
+
+/// Like `reformat`, but with `options` overriding the default rustfmt options
+/// (currently just `fn_single_line=true`).
+fn reformat_with(label: &str, text: &str, options: &[(&str, &str)]) -> Result<String> {
     let _e = pushenv("RUSTUP_TOOLCHAIN", "stable");
     ensure_rustfmt()?;
     let rustfmt_toml = project_root().join("rustfmt.toml");
-    let stdout = cmd!("rustfmt --config-path {rustfmt_toml} --config fn_single_line=true")
+    let mut config = vec![("fn_single_line", "true")];
+    for &(key, value) in options {
+        config.retain(|&(it, _)| it != key);
+        config.push((key, value));
+    }
+    let config = config
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(",");
+    let stdout = cmd!("rustfmt --config-path {rustfmt_toml} --config {config}")
         .stdin(text)
-        .read()?;
+        .read()
+        .with_context(|| {
+            let snippet = text.lines().take(20).collect::<Vec<_>>().join("\n");
+            format!("failed to format generated {}, which starts with:\n{}", label, snippet)
+        })?;
     Ok(format!("//! {}\n\n{}\n", PREAMBLE, stdout))
 }
 
-fn extract_comment_blocks(text: &str) -> Vec<Vec<String>> {
-    do_extract_comment_blocks(text, false).into_iter().map(|(_line, block)| block).collect()
+impl CommentBlock {
+    /// The location of the whole block, from the line with the tag to the
+    /// last line of contents.
+    fn location(&self, file: PathBuf) -> Location {
+        Location::new(file, self.line).with_end_line(self.line + self.contents.len())
+    }
 }
 
-fn extract_comment_blocks_with_empty_lines(tag: &str, text: &str) -> Vec<CommentBlock> {
-    assert!(tag.starts_with(char::is_uppercase));
-    let tag = format!("{}:", tag);
-    let mut res = Vec::new();
-    for (line, mut block) in do_extract_comment_blocks(text, true) {
-        let first = block.remove(0);
-        if first.starts_with(&tag) {
-            let id = first[tag.len()..].trim().to_string();
-            let block = CommentBlock { id, line, contents: block };
-            res.push(block);
-        }
-    }
-    res
+/// Sorts items gathered from comment blocks in several files by id, then
+/// location, so that the output doesn't depend on the order the files were
+/// visited in.
+fn sort_by_id_and_location<T>(items: &mut [T], key: fn(&T) -> (&str, &Location)) {
+    items.sort_by(|lhs, rhs| key(lhs).cmp(&key(rhs)));
 }
 
-struct CommentBlock {
-    id: String,
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Location {
+    file: PathBuf,
     line: usize,
-    contents: Vec<String>,
+    end_line: Option<usize>,
 }
 
-fn do_extract_comment_blocks(
-    text: &str,
-    allow_blocks_with_empty_lines: bool,
-) -> Vec<(usize, Vec<String>)> {
-    let mut res = Vec::new();
-
-    let prefix = "// ";
-    let lines = text.lines().map(str::trim_start);
-
-    let mut block = (0, vec![]);
-    for (line_num, line) in lines.enumerate() {
-        if line == "//" && allow_blocks_with_empty_lines {
-            block.1.push(String::new());
-            continue;
-        }
+impl Location {
+    fn new(file: PathBuf, line: usize) -> Self {
+        Self { file, line, end_line: None }
+    }
 
-        let is_comment = line.starts_with(prefix);
-        if is_comment {
-            block.1.push(line[prefix.len()..].to_string());
-        } else {
-            if !block.1.is_empty() {
-                res.push(mem::take(&mut block));
-            }
-            block.0 = line_num + 2;
-        }
+    fn with_end_line(self, end_line: usize) -> Self {
+        Self { end_line: Some(end_line), ..self }
     }
-    if !block.1.is_empty() {
-        res.push(block)
+
+    /// Renders the location in `style`. `Display` uses `LinkStyle::GitHub`.
+    fn display(&self, style: LinkStyle) -> impl fmt::Display + '_ {
+        LocationDisplay { location: self, style }
     }
-    res
 }
 
-#[derive(Debug)]
-struct Location {
-    file: PathBuf,
-    line: usize,
+/// How to render a `Location`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkStyle {
+    /// An AsciiDoc link to the lines on GitHub, named after the file.
+    GitHub,
+    /// Just the path relative to the project root, for generated code which
+    /// shouldn't change whenever lines move.
+    File,
 }
 
-impl Location {
-    fn new(file: PathBuf, line: usize) -> Self {
-        Self { file, line }
-    }
+struct LocationDisplay<'a> {
+    location: &'a Location,
+    style: LinkStyle,
 }
 
 impl fmt::Display for Location {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let path = self.file.strip_prefix(&project_root()).unwrap().display().to_string();
+        self.display(LinkStyle::GitHub).fmt(f)
+    }
+}
+
+impl fmt::Display for LocationDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Location { file, line, end_line } = self.location;
+        let path = file.strip_prefix(project_root()).unwrap().display().to_string();
         let path = path.replace('\\', "/");
-        let name = self.file.file_name().unwrap();
-        write!(
-            f,
-            "https://github.com/rust-analyzer/rust-analyzer/blob/master/{}#L{}[{}]",
-            path,
-            self.line,
-            name.to_str().unwrap()
-        )
+        match self.style {
+            LinkStyle::GitHub => (),
+            LinkStyle::File => return write!(f, "{}", path),
+        }
+        let name = file.file_name().unwrap();
+        write!(f, "https://github.com/rust-analyzer/rust-analyzer/blob/master/{}#L{}", path, line)?;
+        match *end_line {
+            Some(end_line) if end_line > *line => write!(f, "-L{}", end_line)?,
+            _ => (),
+        }
+        write!(f, "[{}]", name.to_str().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use xshell::rm_rf;
+
+    use super::*;
+
+    /// A directory only `test` uses, so that concurrent `cargo test` runs from
+    /// different checkouts don't clash. It's removed when dropped.
+    struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        fn new(test: &str) -> ScratchDir {
+            let dir = scratch_path(test);
+            let _ = rm_rf(&dir);
+            mkdir_p(&dir).unwrap();
+            ScratchDir(dir)
+        }
+
+        fn join(&self, path: &str) -> PathBuf {
+            self.0.join(path)
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = rm_rf(&self.0);
+        }
+    }
+
+    /// The path of `test`'s `ScratchDir`, for generators which can't capture it.
+    fn scratch_path(test: &str) -> PathBuf {
+        env::temp_dir().join(format!("xtask_codegen_{}_{}", test, process::id()))
+    }
+
+    #[test]
+    fn check_mode_lists_stale_files_without_writing() {
+        let dir = ScratchDir::new("check_mode");
+        let path = dir.join("generated.txt");
+        write_file(&path, "old contents").unwrap();
+
+        let outcomes = vec![
+            (path.clone(), update(&path, "old contents", Mode::Check).unwrap()),
+            (path.clone(), update(&path, "new contents", Mode::Check).unwrap()),
+        ];
+
+        assert_eq!(stale_files(&outcomes), vec![path.clone()]);
+        assert_eq!(read_file(&path).unwrap(), "old contents");
+    }
+
+    #[test]
+    fn verify_mode_reports_missing_files() {
+        let dir = ScratchDir::new("verify_missing");
+        let path = dir.join("generated.rs");
+
+        let err = update(&path, "fn generated() {}", Mode::Verify).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("generated file `{}` is missing, run `cargo xtask codegen`", path.display())
+        );
+    }
+
+    #[test]
+    fn overwrite_mode_creates_missing_directories() {
+        let dir = ScratchDir::new("missing_dir");
+        let path = dir.join("nested/generated.rs");
+
+        update(&path, "fn generated() {}", Mode::Overwrite).unwrap();
+        assert_eq!(read_file(&path).unwrap(), "fn generated() {}");
+    }
+
+    #[test]
+    fn verify_mode_points_stale_generated_files_to_codegen() {
+        let dir = ScratchDir::new("verify_preamble");
+        let path = dir.join("generated.rs");
+        write_file(&path, format!("//! {}\n\nfn hand_edited() {{}}\n", PREAMBLE)).unwrap();
+
+        let err = update(&path, "fn generated() {}", Mode::Verify).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "`{}` is generated and out of date, run `cargo xtask codegen` \
+                 instead of editing it by hand",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn location_links_to_line_range() {
+        let file = project_root().join("xtask/src/codegen.rs");
+        let url = "https://github.com/rust-analyzer/rust-analyzer/blob/master/xtask/src/codegen.rs";
+        assert_eq!(Location::new(file.clone(), 3).to_string(), format!("{}#L3[codegen.rs]", url));
+        assert_eq!(
+            Location::new(file.clone(), 3).with_end_line(7).to_string(),
+            format!("{}#L3-L7[codegen.rs]", url)
+        );
+        assert_eq!(
+            Location::new(file.clone(), 3).with_end_line(3).to_string(),
+            format!("{}#L3[codegen.rs]", url)
+        );
+        assert_eq!(
+            Location::new(file, 3).with_end_line(7).display(LinkStyle::GitHub).to_string(),
+            format!("{}#L3-L7[codegen.rs]", url)
+        );
+    }
+
+    #[test]
+    fn file_locations_are_relative_paths() {
+        let file = project_root().join("xtask/src/codegen.rs");
+        let location = Location::new(file, 3).with_end_line(7);
+        assert_eq!(location.display(LinkStyle::File).to_string(), "xtask/src/codegen.rs");
+    }
+
+    #[test]
+    fn summary_counts_outcomes() {
+        let dir = ScratchDir::new("summary");
+        let (unchanged, updated, stale) =
+            (dir.join("unchanged.txt"), dir.join("updated.txt"), dir.join("stale.txt"));
+        for path in &[&unchanged, &updated, &stale] {
+            write_file(path, "old contents").unwrap();
+        }
+
+        let outcomes = vec![
+            (unchanged.clone(), update(&unchanged, "old contents", Mode::Overwrite).unwrap()),
+            (updated.clone(), update(&updated, "new contents", Mode::Overwrite).unwrap()),
+            (stale.clone(), update(&stale, "new contents", Mode::Check).unwrap()),
+            (unchanged.clone(), update(&unchanged, "old contents", Mode::Check).unwrap()),
+        ];
+
+        let summary = Summary::new(&outcomes);
+        assert_eq!(summary, Summary { updated: 1, unchanged: 2, stale: 1 });
+        assert_eq!(summary.to_string(), "codegen: 1 updated, 2 unchanged, 1 stale");
+    }
+
+    #[test]
+    fn codegen_check_succeeds() {
+        let codegen = flags::Codegen {
+            skip_lint_completions: true,
+            check: true,
+            fail_fast: None,
+            since: None,
+            assist: None,
+        };
+        let stale = stale_files(&codegen.generate(Mode::Check).unwrap());
+        // Generated docs are not committed, so they might be missing.
+        let docs = project_root().join("docs/user");
+        assert!(stale.iter().all(|it| it.starts_with(&docs)), "{:?}", stale);
+    }
+
+    #[test]
+    fn reformat_error_points_at_generator() {
+        let text = "fn broken( {\n    let x = ;\n}\n";
+        let err = reformat("broken test code", text).unwrap_err().to_string();
+        assert!(err.starts_with("failed to format generated broken test code"), "{}", err);
+        assert!(err.contains("fn broken( {\n    let x = ;"), "{}", err);
+    }
+
+    #[test]
+    fn reformat_with_overrides_default_options() {
+        let text = "fn f() -> u32 { 92 }\n";
+        let body = |formatted: String| formatted[formatted.find("\n\n").unwrap() + 2..].to_string();
+        assert_eq!(body(reformat("test", text).unwrap()), "fn f() -> u32 { 92 }\n");
+        assert_eq!(
+            body(reformat_with("test", text, &[("fn_single_line", "false")]).unwrap()),
+            "fn f() -> u32 {\n    92\n}\n"
+        );
+    }
+
+    #[test]
+    fn lint_completions_are_generated_by_default() {
+        let names = |flags: flags::Codegen| {
+            flags.generators().into_iter().map(|target| target.name).collect::<Vec<_>>()
+        };
+        let default = names(flags::Codegen {
+            skip_lint_completions: false,
+            check: false,
+            fail_fast: None,
+            since: None,
+            assist: None,
+        });
+        assert!(default.contains(&"lint completions"), "{:?}", default);
+        let skipped = names(flags::Codegen {
+            skip_lint_completions: true,
+            check: false,
+            fail_fast: None,
+            since: None,
+            assist: None,
+        });
+        assert!(!skipped.contains(&"lint completions"), "{:?}", skipped);
+        assert_eq!(default.len(), skipped.len() + 1);
+    }
+
+    #[test]
+    fn failing_generator_does_not_stop_the_rest_without_fail_fast() {
+        fn broken(_mode: Mode) -> Result<Outcomes> {
+            anyhow::bail!("broken input")
+        }
+        fn working(mode: Mode) -> Result<Outcomes> {
+            let path = scratch_path("fail_fast").join("generated.txt");
+            let outcome = update(&path, "generated", mode)?;
+            Ok(vec![(path, outcome)])
+        }
+        let dir = ScratchDir::new("fail_fast");
+        let path = dir.join("generated.txt");
+        let generators = &[
+            Target { name: "broken", inputs: &[], generate: broken },
+            Target { name: "working", inputs: &[], generate: working },
+        ];
+
+        write_file(&path, "old").unwrap();
+        let err = run_generators(generators, Mode::Overwrite, true).unwrap_err();
+        assert_eq!(err.to_string(), "broken input");
+        assert_eq!(read_file(&path).unwrap(), "old");
+
+        let err = run_generators(generators, Mode::Overwrite, false).unwrap_err();
+        assert_eq!(err.to_string(), "1 generators failed:\nbroken: broken input");
+        assert_eq!(read_file(&path).unwrap(), "generated");
+    }
+
+    #[test]
+    fn since_skips_generators_with_unchanged_inputs() {
+        fn untouched(_mode: Mode) -> Result<Outcomes> {
+            panic!("generator with unchanged inputs was run")
+        }
+        fn touched(mode: Mode) -> Result<Outcomes> {
+            let path = scratch_path("since").join("generated.txt");
+            let outcome = update(&path, "generated", mode)?;
+            Ok(vec![(path, outcome)])
+        }
+        let dir = ScratchDir::new("since");
+        let path = dir.join("generated.txt");
+        write_file(&path, "stale").unwrap();
+        let targets = vec![
+            Target { name: "untouched", inputs: &["crates/untouched"], generate: untouched },
+            Target { name: "touched", inputs: &["crates/touched"], generate: touched },
+        ];
+        let changed =
+            vec!["crates/touched/src/lib.rs".to_string(), "crates/untouched2".to_string()];
+
+        let mut skipped = Vec::new();
+        let targets = affected_targets(targets, &changed, |it| skipped.push(it.name));
+        assert_eq!(skipped, vec!["untouched"]);
+        let err = run_generators(&targets, Mode::Verify, true).unwrap_err();
+        assert_eq!(err.to_string(), format!("`{}` is not up-to-date", path.display()));
+
+        // changes to the shared inputs affect every generator
+        let targets = affected_targets(targets, &["rustfmt.toml".to_string()], |_| ());
+        assert_eq!(targets.len(), 1);
+    }
+
+    #[test]
+    fn changed_since_lists_new_files() {
+        let dir = ScratchDir::new("changed_since");
+        mkdir_p(dir.join("new")).unwrap();
+        write_file(dir.join("committed.txt"), "old").unwrap();
+        {
+            let _d = pushd(&dir.0).unwrap();
+            cmd!("git init -q").run().unwrap();
+            cmd!("git config user.name test").run().unwrap();
+            cmd!("git config user.email test").run().unwrap();
+            cmd!("git add committed.txt").run().unwrap();
+            cmd!("git commit -q --no-gpg-sign -m init").run().unwrap();
+        }
+        write_file(dir.join("committed.txt"), "new").unwrap();
+        write_file(dir.join("new/input.txt"), "").unwrap();
+
+        let mut changed = changed_since(&dir.0, "HEAD").unwrap();
+        changed.sort();
+        assert_eq!(changed, vec!["committed.txt", "new/input.txt"]);
     }
 }
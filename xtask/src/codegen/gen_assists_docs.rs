@@ -2,22 +2,43 @@
 
 use std::{fmt, path::Path};
 
+use anyhow::bail;
+
 use crate::{
-    codegen::{self, extract_comment_blocks_with_empty_lines, reformat, Location, Mode, PREAMBLE},
+    codegen::{self, reformat, LinkStyle, Location, Mode, Outcomes, PREAMBLE},
+    comment_blocks::extract_comment_blocks_with_empty_lines,
     project_root, rust_files_in, Result,
 };
 
-pub(crate) fn generate_assists_tests(mode: Mode) -> Result<()> {
+pub(crate) fn generate_assists_tests(mode: Mode) -> Result<Outcomes> {
     let assists = Assist::collect()?;
     generate_tests(&assists, mode)
 }
 
-pub(crate) fn generate_assists_docs(mode: Mode) -> Result<()> {
+/// Like `generate_assists_tests`, but only regenerates the tests of the assist
+/// `id`, so that the other tests stay as they are, even if out of date.
+pub(crate) fn generate_assist_tests(id: &str, mode: Mode) -> Result<Outcomes> {
+    let assists = Assist::collect()?;
+    if !assists.iter().any(|it| it.id == id) {
+        bail!("no assist with id `{}`", id);
+    }
+    let new = tests_text(&assists)?;
+    let path = project_root().join(TESTS_RS);
+    let contents = match xshell::read_file(&path) {
+        Ok(old) => splice_tests(&old, &new, id),
+        Err(_) => new,
+    };
+    let outcome = codegen::update(&path, &contents, mode)?;
+    Ok(vec![(path, outcome)])
+}
+
+pub(crate) fn generate_assists_docs(mode: Mode) -> Result<Outcomes> {
     let assists = Assist::collect()?;
     let contents = assists.into_iter().map(|it| it.to_string()).collect::<Vec<_>>().join("\n\n");
     let contents = format!("//{}\n{}\n", PREAMBLE, contents.trim());
     let dst = project_root().join("docs/user/generated_assists.adoc");
-    codegen::update(&dst, &contents, mode)
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
 }
 
 #[derive(Debug)]
@@ -35,7 +56,7 @@ impl Assist {
         for path in rust_files_in(&project_root().join("crates/ide_assists/src/handlers")) {
             collect_file(&mut res, path.as_path())?;
         }
-        res.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+        codegen::sort_by_id_and_location(&mut res, |it| (it.id.as_str(), &it.location));
         return Ok(res);
 
         fn collect_file(acc: &mut Vec<Assist>, path: &Path) -> Result<()> {
@@ -43,6 +64,7 @@ impl Assist {
             let comment_blocks = extract_comment_blocks_with_empty_lines("Assist", &text);
 
             for block in comment_blocks {
+                let location = block.location(path.to_path_buf());
                 // FIXME: doesn't support blank lines yet, need to tweak
                 // `extract_comment_blocks` for that.
                 let id = block.id;
@@ -65,7 +87,6 @@ impl Assist {
                 assert_eq!(lines.next().unwrap().as_str(), "->");
                 assert_eq!(lines.next().unwrap().as_str(), "```");
                 let after = take_until(lines.by_ref(), "```");
-                let location = Location::new(path.to_path_buf(), block.line);
                 acc.push(Assist { id, location, doc, before, after })
             }
 
@@ -111,7 +132,53 @@ impl fmt::Display for Assist {
     }
 }
 
-fn generate_tests(assists: &[Assist], mode: Mode) -> Result<()> {
+const TESTS_RS: &str = "crates/ide_assists/src/tests/generated.rs";
+
+fn generate_tests(assists: &[Assist], mode: Mode) -> Result<Outcomes> {
+    let buf = tests_text(assists)?;
+    let path = project_root().join(TESTS_RS);
+    let outcome = codegen::update(&path, &buf, mode)?;
+    Ok(vec![(path, outcome)])
+}
+
+/// Takes the tests of the assist `id` from `new` and the others from `old`,
+/// both texts of generated tests. The tests stay sorted by assist id.
+fn splice_tests(old: &str, new: &str, id: &str) -> String {
+    let (header, old_tests) = split_tests(old);
+    let (_, new_tests) = split_tests(new);
+    let mut tests = old_tests.into_iter().filter(|&(it, _)| it != id).collect::<Vec<_>>();
+    let idx = tests.iter().position(|&(it, _)| it > id).unwrap_or(tests.len());
+    let spliced = new_tests.into_iter().filter(|&(it, _)| it == id);
+    tests.splice(idx..idx, spliced);
+
+    let mut res = header.trim_end().to_string();
+    for (_, test) in tests {
+        res.push_str("\n\n");
+        res.push_str(test.trim_end());
+    }
+    res.push('\n');
+    res
+}
+
+/// Splits generated tests into the text before the first test and the tests,
+/// each with the id of its assist.
+fn split_tests(text: &str) -> (&str, Vec<(&str, &str)>) {
+    const TEST_START: &str = "#[test]\nfn doctest_";
+    let mut starts = text.match_indices(TEST_START).map(|(idx, _)| idx).collect::<Vec<_>>();
+    let header = &text[..starts.first().copied().unwrap_or(text.len())];
+    starts.push(text.len());
+    let tests = starts
+        .windows(2)
+        .map(|window| {
+            let test = &text[window[0]..window[1]];
+            let name = &test[TEST_START.len()..];
+            (&name[..name.find('(').unwrap_or(0)], test)
+        })
+        .collect();
+    (header, tests)
+}
+
+fn tests_text(assists: &[Assist]) -> Result<String> {
     let mut buf = String::from("use super::check_doc_test;\n");
 
     for assist in assists.iter() {
@@ -119,6 +186,7 @@ fn generate_tests(assists: &[Assist], mode: Mode) -> Result<()> {
             r######"
 #[test]
 fn doctest_{}() {{
+    // location: {}
     check_doc_test(
         "{}",
 r#####"
@@ -127,6 +195,7 @@ r#####"
 }}
 "######,
             assist.id,
+            assist.location.display(LinkStyle::File),
             assist.id,
             reveal_hash_comments(&assist.before),
             reveal_hash_comments(&assist.after)
@@ -134,8 +203,7 @@ r#####"
 
         buf.push_str(&test)
     }
-    let buf = reformat(&buf)?;
-    codegen::update(&project_root().join("crates/ide_assists/src/tests/generated.rs"), &buf, mode)
+    reformat("assists tests", &buf)
 }
 
 fn hide_hash_comments(text: &str) -> String {
@@ -159,3 +227,46 @@ fn reveal_hash_comments(text: &str) -> String {
         .map(|it| format!("{}\n", it))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splicing_replaces_only_the_targeted_tests() {
+        let test = |id: &str, body: &str| {
+            format!(
+                "#[test]\nfn doctest_{}() {{\n    check_doc_test(\"{}\", {})\n}}\n",
+                id, id, body
+            )
+        };
+        let header = "//! preamble\n\nuse super::check_doc_test;\n\n";
+        let file = |tests: &[String]| format!("{}{}", header, tests.join("\n"));
+        let old = file(&[test("a", "old"), test("c", "old")]);
+        let new = file(&[test("a", "new"), test("b", "new"), test("c", "new")]);
+
+        let with_b = splice_tests(&old, &new, "b");
+        assert_eq!(with_b, file(&[test("a", "old"), test("b", "new"), test("c", "old")]));
+        let with_c = splice_tests(&with_b, &new, "c");
+        assert_eq!(with_c, file(&[test("a", "old"), test("b", "new"), test("c", "new")]));
+        assert_eq!(splice_tests(&new, &new, "a"), new);
+    }
+
+    #[test]
+    fn generated_test_links_to_assist() {
+        let path = project_root().join("crates/ide_assists/src/handlers/flip_comma.rs");
+        let assist = Assist {
+            id: "flip_comma".to_string(),
+            location: Location::new(path, 7),
+            doc: "Flips two comma-separated items.".to_string(),
+            before: "fn main() {\n    ((1, 2),$0 (3, 4));\n}\n".to_string(),
+            after: "fn main() {\n    ((3, 4), (1, 2));\n}\n".to_string(),
+        };
+        let text = tests_text(&[assist]).unwrap();
+        assert!(
+            text.contains("    // location: crates/ide_assists/src/handlers/flip_comma.rs\n"),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Generates the sample types for the `ToChalk` round-trip test from a compact
+//! notation, one type per line.
+//!
+//! The notation is a subset of Rust type syntax: scalars, `str`, `!`, tuples,
+//! slices, arrays (`[T; _]`), references, raw pointers and fn pointers
+//! (`unsafe fn(A, ...) -> R`). `_` is the unknown type and `^d.i` is the
+//! `i`th bound variable of the binder `d` levels out.
+
+use anyhow::{bail, format_err};
+
+use crate::{
+    codegen::{self, reformat_with, Mode, Outcomes},
+    project_root, Result,
+};
+
+const SPEC: &str = "crates/hir_ty/src/traits/chalk/mapping/tests/fixtures.txt";
+const DST: &str = "crates/hir_ty/src/traits/chalk/mapping/tests/fixtures.rs";
+
+pub(crate) fn generate_chalk_fixtures(mode: Mode) -> Result<Outcomes> {
+    let spec = xshell::read_file(project_root().join(SPEC))?;
+    let mut buf = String::from(
+        "/// The types in `fixtures.txt`, each with its line there.\n\
+         pub(super) fn sample_tys() -> Vec<(&'static str, crate::Ty)> {\n    vec![\n",
+    );
+    for line in spec.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let ty = parse_ty(line).map_err(|err| format_err!("{}: `{}`: {}", SPEC, line, err))?;
+        buf.push_str(&format!("({:?}, {}),\n", line, ty));
+    }
+    buf.push_str("]\n}\n");
+    let contents = reformat_with("ToChalk fixtures", &buf, &[("fn_single_line", "false")])?;
+    let dst = project_root().join(DST);
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
+}
+
+/// Parses a type in the fixture notation into the Rust expression building it.
+fn parse_ty(text: &str) -> Result<String> {
+    let mut parser = Parser { tokens: tokenize(text), pos: 0 };
+    let ty = parser.ty()?;
+    match parser.peek() {
+        None => Ok(ty),
+        Some(token) => bail!("unexpected `{}`", token),
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut token = c.to_string();
+        if c.is_alphanumeric() {
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric()) {
+                token.push(c);
+                chars.next();
+            }
+        } else if c == '-' && chars.peek() == Some(&'>') {
+            token.push(chars.next().unwrap());
+        } else if c == '.' && chars.peek() == Some(&'.') {
+            while chars.peek() == Some(&'.') {
+                token.push(chars.next().unwrap());
+            }
+        }
+        res.push(token);
+    }
+    res
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn bump(&mut self) -> Result<String> {
+        let token =
+            self.tokens.get(self.pos).cloned().ok_or_else(|| format_err!("unexpected end"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let res = self.peek() == Some(token);
+        if res {
+            self.pos += 1;
+        }
+        res
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if !self.eat(token) {
+            bail!("expected `{}`", token);
+        }
+        Ok(())
+    }
+
+    fn ty(&mut self) -> Result<String> {
+        let token = self.bump()?;
+        let res = match token.as_str() {
+            "_" => "crate::Ty::Unknown".to_string(),
+            "!" => "crate::Ty::Never".to_string(),
+            "str" => "crate::Ty::Str".to_string(),
+            "(" => {
+                let tys = self.ty_list(")")?;
+                format!("crate::Ty::Tuple({}, {})", tys.len(), substs(&tys))
+            }
+            "[" => {
+                let ty = self.ty()?;
+                let kind = if self.eat(";") {
+                    self.expect("_")?;
+                    "Array"
+                } else {
+                    "Slice"
+                };
+                self.expect("]")?;
+                format!("crate::Ty::{}(crate::Substs::single({}))", kind, ty)
+            }
+            "&" => {
+                let mutability = if self.eat("mut") { "Mut" } else { "Not" };
+                let ty = self.ty()?;
+                format!(
+                    "crate::Ty::Ref(crate::Mutability::{}, crate::Substs::single({}))",
+                    mutability, ty
+                )
+            }
+            "*" => {
+                let mutability = match self.bump()?.as_str() {
+                    "const" => "Not",
+                    "mut" => "Mut",
+                    other => bail!("expected `const` or `mut`, got `{}`", other),
+                };
+                let ty = self.ty()?;
+                format!(
+                    "crate::Ty::Raw(crate::Mutability::{}, crate::Substs::single({}))",
+                    mutability, ty
+                )
+            }
+            "unsafe" => {
+                self.expect("fn")?;
+                self.fn_ptr("Unsafe")?
+            }
+            "fn" => self.fn_ptr("Safe")?,
+            "^" => {
+                let debruijn = self.bump()?;
+                self.expect(".")?;
+                let index = self.bump()?;
+                if !debruijn.chars().chain(index.chars()).all(|c| c.is_ascii_digit()) {
+                    bail!("expected a bound var like `^0.1`");
+                }
+                format!(
+                    "crate::Ty::BoundVar(crate::BoundVar::new(chalk_ir::DebruijnIndex::new({}), {}))",
+                    debruijn, index
+                )
+            }
+            other => format!("crate::Ty::Scalar({})", scalar(other)?),
+        };
+        Ok(res)
+    }
+
+    /// Parses comma-separated types up to and including `close`.
+    fn ty_list(&mut self, close: &str) -> Result<Vec<String>> {
+        let mut res = Vec::new();
+        while !self.eat(close) {
+            res.push(self.ty()?);
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(res)
+    }
+
+    fn fn_ptr(&mut self, safety: &str) -> Result<String> {
+        self.expect("(")?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        while !self.eat(")") {
+            if self.eat("...") {
+                variadic = true;
+                self.expect(")")?;
+                break;
+            }
+            params.push(self.ty()?);
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
+        }
+        let num_args = params.len();
+        let ret = if self.eat("->") { self.ty()? } else { "crate::Ty::unit()".to_string() };
+        params.push(ret);
+        Ok(format!(
+            "crate::Ty::Function(crate::FnPointer {{ num_args: {}, sig: crate::FnSig {{ \
+             variadic: {}, safety: crate::Safety::{} }}, substs: {} }})",
+            num_args,
+            variadic,
+            safety,
+            substs(&params)
+        ))
+    }
+}
+
+fn substs(tys: &[String]) -> String {
+    if tys.is_empty() {
+        return "crate::Substs::empty()".to_string();
+    }
+    format!("crate::Substs(vec![{}].into())", tys.join(", "))
+}
+
+fn scalar(name: &str) -> Result<String> {
+    let res = match name {
+        "bool" => "crate::Scalar::Bool".to_string(),
+        "char" => "crate::Scalar::Char".to_string(),
+        "f32" | "f64" => {
+            format!("crate::Scalar::Float(crate::primitive::FloatTy::{})", capitalize(name))
+        }
+        "isize" | "i8" | "i16" | "i32" | "i64" | "i128" => {
+            format!("crate::Scalar::Int(crate::primitive::IntTy::{})", capitalize(name))
+        }
+        "usize" | "u8" | "u16" | "u32" | "u64" | "u128" => {
+            format!("crate::Scalar::Uint(crate::primitive::UintTy::{})", capitalize(name))
+        }
+        _ => bail!("unknown type `{}`", name),
+    };
+    Ok(res)
+}
+
+fn capitalize(name: &str) -> String {
+    name[..1].to_uppercase() + &name[1..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fixture_notation() {
+        assert_eq!(
+            parse_ty("u8").unwrap(),
+            "crate::Ty::Scalar(crate::Scalar::Uint(crate::primitive::UintTy::U8))"
+        );
+        assert_eq!(
+            parse_ty("&mut [str]").unwrap(),
+            "crate::Ty::Ref(crate::Mutability::Mut, \
+             crate::Substs::single(crate::Ty::Slice(crate::Substs::single(crate::Ty::Str))))"
+        );
+        assert_eq!(
+            parse_ty("(!, _)").unwrap(),
+            "crate::Ty::Tuple(2, crate::Substs(vec![crate::Ty::Never, crate::Ty::Unknown].into()))"
+        );
+        assert_eq!(
+            parse_ty("unsafe fn(^0.1, ...)").unwrap(),
+            "crate::Ty::Function(crate::FnPointer { num_args: 1, sig: crate::FnSig { \
+             variadic: true, safety: crate::Safety::Unsafe }, substs: crate::Substs(vec![\
+             crate::Ty::BoundVar(crate::BoundVar::new(chalk_ir::DebruijnIndex::new(0), 1)), \
+             crate::Ty::unit()].into()) })"
+        );
+        assert_eq!(parse_ty("()").unwrap(), "crate::Ty::Tuple(0, crate::Substs::empty())");
+        assert_eq!(parse_ty("[u8; 4]").unwrap_err().to_string(), "expected `_`");
+        assert_eq!(parse_ty("&Foo").unwrap_err().to_string(), "unknown type `Foo`");
+        assert_eq!(parse_ty("(u8,) u8").unwrap_err().to_string(), "unexpected `u8`");
+    }
+}
@@ -1,20 +1,24 @@
 //! Generates `assists.md` documentation.
 
-use std::{fmt, path::PathBuf};
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+use anyhow::bail;
 
 use crate::{
-    codegen::{self, extract_comment_blocks_with_empty_lines, Location, Mode, PREAMBLE},
+    codegen::{self, Location, Mode, Outcomes, PREAMBLE},
+    comment_blocks::extract_comment_blocks_with_empty_lines,
     project_root, rust_files, Result,
 };
 
-pub(crate) fn generate_diagnostic_docs(mode: Mode) -> Result<()> {
+pub(crate) fn generate_diagnostic_docs(mode: Mode) -> Result<Outcomes> {
     let diagnostics = Diagnostic::collect()?;
+    check_all_documented(&diagnostics)?;
     let contents =
         diagnostics.into_iter().map(|it| it.to_string()).collect::<Vec<_>>().join("\n\n");
     let contents = format!("//{}\n{}\n", PREAMBLE, contents.trim());
     let dst = project_root().join("docs/user/generated_diagnostic.adoc");
-    codegen::update(&dst, &contents, mode)?;
-    Ok(())
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
 }
 
 #[derive(Debug)]
@@ -30,28 +34,65 @@ impl Diagnostic {
         for path in rust_files() {
             collect_file(&mut res, path)?;
         }
-        res.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+        codegen::sort_by_id_and_location(&mut res, |it| (it.id.as_str(), &it.location));
         return Ok(res);
 
         fn collect_file(acc: &mut Vec<Diagnostic>, path: PathBuf) -> Result<()> {
             let text = xshell::read_file(&path)?;
-            let comment_blocks = extract_comment_blocks_with_empty_lines("Diagnostic", &text);
-
-            for block in comment_blocks {
-                let id = block.id;
-                if let Err(msg) = is_valid_diagnostic_name(&id) {
-                    panic!("invalid diagnostic name: {:?}:\n  {}", id, msg)
-                }
-                let doc = block.contents.join("\n");
-                let location = Location::new(path.clone(), block.line);
-                acc.push(Diagnostic { id, location, doc })
-            }
-
+            collect_text(acc, path, &text);
             Ok(())
         }
     }
 }
 
+fn collect_text(acc: &mut Vec<Diagnostic>, path: PathBuf, text: &str) {
+    let comment_blocks = extract_comment_blocks_with_empty_lines("Diagnostic", text);
+
+    for block in comment_blocks {
+        let location = block.location(path.clone());
+        let id = block.id;
+        if let Err(msg) = is_valid_diagnostic_name(&id) {
+            panic!("invalid diagnostic name: {:?}:\n  {}", id, msg)
+        }
+        let doc = block.contents.join("\n");
+        acc.push(Diagnostic { id, location, doc })
+    }
+}
+
+/// Makes sure that every `DiagnosticCode` used in the code base has a
+/// corresponding `// Diagnostic:` doc block.
+fn check_all_documented(diagnostics: &[Diagnostic]) -> Result<()> {
+    let mut codes = Vec::new();
+    for path in rust_files() {
+        let text = xshell::read_file(&path)?;
+        codes.extend(diagnostic_codes(&text));
+    }
+    check_documented(diagnostics, codes)
+}
+
+fn check_documented(diagnostics: &[Diagnostic], codes: Vec<String>) -> Result<()> {
+    let documented: HashSet<&str> = diagnostics.iter().map(|it| it.id.as_str()).collect();
+    let mut missing: Vec<String> =
+        codes.into_iter().filter(|code| !documented.contains(code.as_str())).collect();
+    missing.sort();
+    missing.dedup();
+    if !missing.is_empty() {
+        bail!("diagnostics without a `// Diagnostic:` doc comment block: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+fn diagnostic_codes(text: &str) -> Vec<String> {
+    let marker = "DiagnosticCode(\"";
+    text.match_indices(marker)
+        .filter_map(|(idx, _)| {
+            let rest = &text[idx + marker.len()..];
+            let end = rest.find('"')?;
+            Some(rest[..end].to_string())
+        })
+        .collect()
+}
+
 fn is_valid_diagnostic_name(diagnostic: &str) -> Result<(), String> {
     let diagnostic = diagnostic.trim();
     if diagnostic.find(char::is_whitespace).is_some() {
@@ -72,3 +113,60 @@ impl fmt::Display for Diagnostic {
         writeln!(f, "=== {}\n**Source:** {}\n{}", self.id, self.location, self.doc)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undocumented_diagnostic_is_reported() {
+        let text = r#"
+// Diagnostic: documented-diagnostic
+//
+// This diagnostic has docs.
+struct Documented;
+
+impl Diagnostic for Documented {
+    fn code(&self) -> DiagnosticCode {
+        DiagnosticCode("documented-diagnostic")
+    }
+}
+
+struct Undocumented;
+
+impl Diagnostic for Undocumented {
+    fn code(&self) -> DiagnosticCode {
+        DiagnosticCode("undocumented-diagnostic")
+    }
+}
+"#;
+        let mut diagnostics = Vec::new();
+        collect_text(&mut diagnostics, PathBuf::from("fake.rs"), text);
+        let codes = diagnostic_codes(text);
+        assert_eq!(codes, vec!["documented-diagnostic", "undocumented-diagnostic"]);
+
+        let err = check_documented(&diagnostics, codes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "diagnostics without a `// Diagnostic:` doc comment block: undocumented-diagnostic"
+        );
+    }
+    #[test]
+    fn output_does_not_depend_on_file_order() {
+        let a =
+            (project_root().join("a.rs"), "// Diagnostic: same-id\n//\n// From a.\nstruct A;\n");
+        let b =
+            (project_root().join("b.rs"), "// Diagnostic: same-id\n//\n// From b.\nstruct B;\n");
+        let render = |files: &[&(PathBuf, &str)]| {
+            let mut diagnostics = Vec::new();
+            for (path, text) in files {
+                collect_text(&mut diagnostics, path.clone(), text);
+            }
+            codegen::sort_by_id_and_location(&mut diagnostics, |it| (it.id.as_str(), &it.location));
+            diagnostics.iter().map(|it| it.to_string()).collect::<Vec<_>>()
+        };
+        let forward = render(&[&a, &b]);
+        assert_eq!(forward, render(&[&b, &a]));
+        assert!(forward[0].contains("From a."));
+    }
+}
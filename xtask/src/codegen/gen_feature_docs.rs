@@ -3,17 +3,18 @@
 use std::{fmt, path::PathBuf};
 
 use crate::{
-    codegen::{self, extract_comment_blocks_with_empty_lines, Location, Mode, PREAMBLE},
+    codegen::{self, Location, Mode, Outcomes, PREAMBLE},
+    comment_blocks::extract_comment_blocks_with_empty_lines,
     project_root, rust_files, Result,
 };
 
-pub(crate) fn generate_feature_docs(mode: Mode) -> Result<()> {
+pub(crate) fn generate_feature_docs(mode: Mode) -> Result<Outcomes> {
     let features = Feature::collect()?;
     let contents = features.into_iter().map(|it| it.to_string()).collect::<Vec<_>>().join("\n\n");
     let contents = format!("//{}\n{}\n", PREAMBLE, contents.trim());
     let dst = project_root().join("docs/user/generated_features.adoc");
-    codegen::update(&dst, &contents, mode)?;
-    Ok(())
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
 }
 
 #[derive(Debug)]
@@ -29,7 +30,7 @@ impl Feature {
         for path in rust_files() {
             collect_file(&mut res, path)?;
         }
-        res.sort_by(|lhs, rhs| lhs.id.cmp(&rhs.id));
+        codegen::sort_by_id_and_location(&mut res, |it| (it.id.as_str(), &it.location));
         return Ok(res);
 
         fn collect_file(acc: &mut Vec<Feature>, path: PathBuf) -> Result<()> {
@@ -37,12 +38,12 @@ impl Feature {
             let comment_blocks = extract_comment_blocks_with_empty_lines("Feature", &text);
 
             for block in comment_blocks {
+                let location = block.location(path.clone());
                 let id = block.id;
                 if let Err(msg) = is_valid_feature_name(&id) {
                     panic!("invalid feature name: {:?}:\n  {}", id, msg)
                 }
                 let doc = block.contents.join("\n");
-                let location = Location::new(path.clone(), block.line);
                 acc.push(Feature { id, location, doc })
             }
 
@@ -6,11 +6,11 @@ use walkdir::WalkDir;
 use xshell::{cmd, read_file};
 
 use crate::{
-    codegen::{project_root, reformat, update, Mode, Result},
+    codegen::{project_root, reformat, update, Mode, Outcomes, Result},
     run_rustfmt,
 };
 
-pub(crate) fn generate_lint_completions(mode: Mode) -> Result<()> {
+pub(crate) fn generate_lint_completions(mode: Mode) -> Result<Outcomes> {
     if !Path::new("./target/rust").exists() {
         cmd!("git clone --depth=1 https://github.com/rust-lang/rust ./target/rust").run()?;
     }
@@ -21,14 +21,14 @@ pub(crate) fn generate_lint_completions(mode: Mode) -> Result<()> {
 
     cmd!("curl http://rust-lang.github.io/rust-clippy/master/lints.json --output ./target/clippy_lints.json").run()?;
     generate_descriptor_clippy(&mut contents, &Path::new("./target/clippy_lints.json"))?;
-    let contents = reformat(&contents)?;
+    let contents = reformat("lint completions", &contents)?;
 
     let destination =
         project_root().join("crates/ide_completion/src/generated_lint_completions.rs");
-    update(destination.as_path(), &contents, mode)?;
+    let outcome = update(destination.as_path(), &contents, mode)?;
     run_rustfmt(mode)?;
 
-    Ok(())
+    Ok(vec![(destination, outcome)])
 }
 
 fn generate_descriptor(buf: &mut String, src_dir: PathBuf) -> Result<()> {
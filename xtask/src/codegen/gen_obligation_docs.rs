@@ -0,0 +1,114 @@
+//! Generates a reference of the goals we can ask the trait solver to prove,
+//! one per variant of `hir_ty::traits::Obligation`.
+
+use anyhow::bail;
+
+use crate::{
+    codegen::{self, Mode, Outcomes, PREAMBLE},
+    project_root, Result,
+};
+
+const TRAITS_RS: &str = "crates/hir_ty/src/traits.rs";
+const DST: &str = "docs/dev/generated_obligations.md";
+
+pub(crate) fn generate_obligation_docs(mode: Mode) -> Result<Outcomes> {
+    let traits_rs = xshell::read_file(project_root().join(TRAITS_RS))?;
+    let contents = obligations_text(&traits_rs)?;
+    let dst = project_root().join(DST);
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
+}
+
+fn obligations_text(traits_rs: &str) -> Result<String> {
+    let mut buf = format!(
+        "<!-- {} -->\n\n# Solver goals\n\n\
+         The kinds of `Obligation` in `{}`, i.e. the questions we can ask the trait solver.\n",
+        PREAMBLE, TRAITS_RS
+    );
+    for variant in Variant::collect(traits_rs)? {
+        buf.push_str(&format!("\n## `{}`\n", variant.signature));
+        if !variant.doc.is_empty() {
+            buf.push_str(&format!("\n{}\n", variant.doc));
+        }
+    }
+    Ok(buf)
+}
+
+#[derive(Debug)]
+struct Variant {
+    /// The variant as written, e.g. `Trait(TraitRef)`.
+    signature: String,
+    doc: String,
+}
+
+impl Variant {
+    fn collect(traits_rs: &str) -> Result<Vec<Variant>> {
+        let mut lines = traits_rs.lines().skip_while(|&line| line != "pub enum Obligation {");
+        if lines.next().is_none() {
+            bail!("`pub enum Obligation` not found in {}", TRAITS_RS);
+        }
+        let mut res = Vec::new();
+        let mut doc = Vec::new();
+        for line in lines.take_while(|&line| line != "}").map(str::trim) {
+            if let Some(doc_line) = line.strip_prefix("///") {
+                doc.push(doc_line.strip_prefix(' ').unwrap_or(doc_line));
+            } else if line.starts_with(|c: char| c.is_ascii_uppercase()) {
+                let signature = line.trim_end_matches(',').to_string();
+                res.push(Variant { signature, doc: doc.join("\n") });
+                doc.clear();
+            } else if !line.starts_with("#[") {
+                doc.clear();
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    const TRAITS_RS_TEXT: &str = r#"
+/// Something that needs to be proven.
+pub enum Obligation {
+    /// Prove that a certain type implements a trait (the type is the `Self` type
+    /// parameter to the `TraitRef`).
+    Trait(TraitRef),
+    Projection(ProjectionPredicate),
+}
+"#;
+
+    #[test]
+    fn lists_obligation_variants_with_their_docs() {
+        let text = obligations_text(TRAITS_RS_TEXT).unwrap();
+        let body = &text[text.find("\n## ").unwrap()..];
+        assert_eq!(
+            body,
+            "\n## `Trait(TraitRef)`\n\n\
+             Prove that a certain type implements a trait (the type is the `Self` type\n\
+             parameter to the `TraitRef`).\n\
+             \n## `Projection(ProjectionPredicate)`\n"
+        );
+    }
+
+    #[test]
+    fn new_variant_requires_regenerating() {
+        let path = env::temp_dir().join("xtask_codegen_obligations.md");
+        xshell::write_file(&path, obligations_text(TRAITS_RS_TEXT).unwrap()).unwrap();
+
+        let with_variant = TRAITS_RS_TEXT.replace(
+            "    Projection(ProjectionPredicate),\n",
+            "    Projection(ProjectionPredicate),\n    /// Prove that a type is well-formed.\n    WellFormed(Ty),\n",
+        );
+        let text = obligations_text(&with_variant).unwrap();
+        assert!(text.ends_with("\n## `WellFormed(Ty)`\n\nProve that a type is well-formed.\n"));
+        assert!(codegen::update(&path, &text, Mode::Verify).is_err());
+    }
+
+    #[test]
+    fn missing_obligation_enum_is_an_error() {
+        assert!(obligations_text("pub enum Goal {\n}\n").is_err());
+    }
+}
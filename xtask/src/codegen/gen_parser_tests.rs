@@ -8,13 +8,14 @@ use std::{
 };
 
 use crate::{
-    codegen::{extract_comment_blocks, update, Mode},
+    codegen::{update, Mode, Outcomes},
+    comment_blocks::extract_comment_blocks,
     project_root, Result,
 };
 
-pub(crate) fn generate_parser_tests(mode: Mode) -> Result<()> {
+pub(crate) fn generate_parser_tests(mode: Mode) -> Result<Outcomes> {
     let tests = tests_from_dir(&project_root().join(Path::new("crates/parser/src/grammar")))?;
-    fn install_tests(tests: &HashMap<String, Test>, into: &str, mode: Mode) -> Result<()> {
+    fn install_tests(tests: &HashMap<String, Test>, into: &str, mode: Mode) -> Result<Outcomes> {
         let tests_dir = project_root().join(into);
         if !tests_dir.is_dir() {
             fs::create_dir_all(&tests_dir)?;
@@ -25,6 +26,7 @@ pub(crate) fn generate_parser_tests(mode: Mode) -> Result<()> {
             panic!("Test is deleted: {}", t);
         }
 
+        let mut outcomes = Vec::new();
         let mut new_idx = existing.len() + 1;
         for (name, test) in tests {
             let path = match existing.get(name) {
@@ -35,12 +37,14 @@ pub(crate) fn generate_parser_tests(mode: Mode) -> Result<()> {
                     tests_dir.join(file_name)
                 }
             };
-            update(&path, &test.text, mode)?;
+            let outcome = update(&path, &test.text, mode)?;
+            outcomes.push((path, outcome));
         }
-        Ok(())
+        Ok(outcomes)
     }
-    install_tests(&tests.ok, "crates/syntax/test_data/parser/inline/ok", mode)?;
-    install_tests(&tests.err, "crates/syntax/test_data/parser/inline/err", mode)
+    let mut outcomes = install_tests(&tests.ok, "crates/syntax/test_data/parser/inline/ok", mode)?;
+    outcomes.extend(install_tests(&tests.err, "crates/syntax/test_data/parser/inline/err", mode)?);
+    Ok(outcomes)
 }
 
 #[derive(Debug)]
@@ -132,3 +136,30 @@ fn existing_tests(dir: &Path, ok: bool) -> Result<HashMap<String, (PathBuf, Test
     }
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_err_blocks_are_error_tests() {
+        let text = r#"
+// test item_ok
+// fn f() {}
+fn a() {}
+
+// test_err item_err
+// fn f( {}
+fn b() {}
+"#;
+        let tests =
+            collect_tests(text).into_iter().map(|it| (it.name, it.text, it.ok)).collect::<Vec<_>>();
+        assert_eq!(
+            tests,
+            vec![
+                ("item_ok".to_string(), "fn f() {}\n".to_string(), true),
+                ("item_err".to_string(), "fn f( {}\n".to_string(), false),
+            ]
+        );
+    }
+}
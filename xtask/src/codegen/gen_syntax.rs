@@ -10,31 +10,54 @@ use std::{
 
 use proc_macro2::{Punct, Spacing};
 use quote::{format_ident, quote};
-use ungrammar::{rust_grammar, Grammar, Rule};
+use ungrammar::{Grammar, Rule};
 
 use crate::{
     ast_src::{AstEnumSrc, AstNodeSrc, AstSrc, Cardinality, Field, KindsSrc, KINDS_SRC},
-    codegen::{reformat, update, Mode},
+    codegen::{reformat, update, Mode, Outcomes},
     project_root, Result,
 };
 
-pub(crate) fn generate_syntax(mode: Mode) -> Result<()> {
-    let grammar = rust_grammar();
+pub(crate) fn generate_syntax(mode: Mode) -> Result<Outcomes> {
+    let grammar_src = xshell::read_file(project_root().join("crates/syntax/rust.ungram"))?;
+    let grammar: Grammar = grammar_src.parse()?;
     let ast = lower(&grammar);
 
     let syntax_kinds_file = project_root().join("crates/parser/src/syntax_kind/generated.rs");
     let syntax_kinds = generate_syntax_kinds(KINDS_SRC)?;
-    update(syntax_kinds_file.as_path(), &syntax_kinds, mode)?;
+    let syntax_kinds_outcome = update(syntax_kinds_file.as_path(), &syntax_kinds, mode)?;
 
     let ast_tokens_file = project_root().join("crates/syntax/src/ast/generated/tokens.rs");
     let contents = generate_tokens(&ast)?;
-    update(ast_tokens_file.as_path(), &contents, mode)?;
+    let ast_tokens_outcome = update(ast_tokens_file.as_path(), &contents, mode)?;
 
     let ast_nodes_file = project_root().join("crates/syntax/src/ast/generated/nodes.rs");
-    let contents = generate_nodes(KINDS_SRC, &ast)?;
-    update(ast_nodes_file.as_path(), &contents, mode)?;
+    let contents = with_grammar_hash(generate_nodes(KINDS_SRC, &ast)?, &grammar_src);
+    let ast_nodes_outcome = update(ast_nodes_file.as_path(), &contents, mode)?;
+
+    Ok(vec![
+        (syntax_kinds_file, syntax_kinds_outcome),
+        (ast_tokens_file, ast_tokens_outcome),
+        (ast_nodes_file, ast_nodes_outcome),
+    ])
+}
 
-    Ok(())
+/// Puts a `// grammar-hash: <hash>` line below the preamble of `contents`, so
+/// that tools can tell the grammar changed without diffing the whole file.
+fn with_grammar_hash(contents: String, grammar_src: &str) -> String {
+    let line = format!("// grammar-hash: {:016x}\n", grammar_hash(grammar_src));
+    match contents.find("\n\n") {
+        Some(idx) => format!("{}\n{}{}", &contents[..idx], line, &contents[idx + 1..]),
+        None => line + &contents,
+    }
+}
+
+/// FNV-1a over the grammar source. Unlike `DefaultHasher`, this doesn't change
+/// between Rust releases, so the hash only changes with the grammar.
+fn grammar_hash(grammar_src: &str) -> u64 {
+    grammar_src
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x100_0000_01b3))
 }
 
 fn generate_tokens(grammar: &AstSrc) -> Result<String> {
@@ -62,6 +85,7 @@ fn generate_tokens(grammar: &AstSrc) -> Result<String> {
     });
 
     let pretty = reformat(
+        "syntax tokens",
         &quote! {
             use crate::{SyntaxKind::{self, *}, SyntaxToken, ast::AstToken};
             #(#tokens)*
@@ -264,7 +288,7 @@ fn generate_nodes(kinds: KindsSrc<'_>, grammar: &AstSrc) -> Result<String> {
         }
     }
 
-    let pretty = reformat(&res)?;
+    let pretty = reformat("syntax nodes", &res)?;
     Ok(pretty)
 }
 
@@ -386,7 +410,7 @@ fn generate_syntax_kinds(grammar: KindsSrc<'_>) -> Result<String> {
         }
     };
 
-    reformat(&ast.to_string())
+    reformat("syntax kinds", &ast.to_string())
 }
 
 fn to_upper_snake_case(s: &str) -> String {
@@ -747,3 +771,20 @@ impl AstNodeSrc {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grammar_hash_follows_the_grammar() {
+        let hash_line = |src: &str| {
+            let contents = with_grammar_hash("//! preamble\n\nfn f() {}\n".to_string(), src);
+            assert!(contents.starts_with("//! preamble\n// grammar-hash: "), "{}", contents);
+            contents.lines().nth(1).unwrap().to_string()
+        };
+        let grammar = "Name = 'ident'\nPath = Name ('::' Name)*";
+        assert_eq!(hash_line(grammar), hash_line(grammar));
+        assert_ne!(hash_line(grammar), hash_line("Name = 'ident'\nPath = Name ('.' Name)*"));
+    }
+}
@@ -0,0 +1,319 @@
+//! Generates a checklist of the `ToChalk` impls in `hir_ty`, flagging
+//! conversion directions which are not implemented or may panic, including
+//! through the free functions in `mapping.rs` they call.
+//!
+//! Also checks that every `Ty` variant is handled by both directions of
+//! `impl ToChalk for Ty`, since only `to_chalk`'s match is exhaustive.
+
+use std::{collections::HashMap, fmt};
+
+use anyhow::bail;
+
+use crate::{
+    codegen::{self, Mode, Outcomes, PREAMBLE},
+    project_root, Result,
+};
+
+const MAPPING_RS: &str = "crates/hir_ty/src/traits/chalk/mapping.rs";
+const LIB_RS: &str = "crates/hir_ty/src/lib.rs";
+
+/// `Ty` variants which Chalk never hands back to us, so `from_chalk` doesn't
+/// produce them.
+const NOT_FROM_CHALK: &[&str] = &[
+    // Chalk's inference variables are its own, we convert them to `Ty::Unknown`
+    "InferenceVar",
+];
+
+pub(crate) fn generate_to_chalk_audit(mode: Mode) -> Result<Outcomes> {
+    let text = xshell::read_file(project_root().join(MAPPING_RS))?;
+    let lib_rs = xshell::read_file(project_root().join(LIB_RS))?;
+    let missing = unconverted_ty_variants(&lib_rs, &text);
+    if !missing.is_empty() {
+        bail!("`impl ToChalk for Ty` in {} doesn't handle {}", MAPPING_RS, missing.join(", "));
+    }
+    let contents = audit_text(&text);
+    let dst = project_root().join("docs/dev/generated_to_chalk_audit.md");
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
+}
+
+fn audit_text(mapping_rs: &str) -> String {
+    let impls = ToChalkImpl::collect(mapping_rs);
+    let mut buf = format!(
+        "<!-- {} -->\n\n# `ToChalk` impls\n\nConversions in `{}`. \
+         Unchecked entries have a direction which is unimplemented or may panic.\n\n",
+        PREAMBLE, MAPPING_RS
+    );
+    for it in impls {
+        buf.push_str(&format!("{}\n", it));
+    }
+    buf
+}
+
+/// Lists the variants of `pub enum Ty` in `lib_rs` which aren't mentioned in
+/// the `to_chalk` or the `from_chalk` half of `impl ToChalk for Ty`.
+fn unconverted_ty_variants(lib_rs: &str, mapping_rs: &str) -> Vec<String> {
+    let variants = lib_rs
+        .lines()
+        .skip_while(|&line| line != "pub enum Ty {")
+        .skip(1)
+        .take_while(|&line| line != "}")
+        .map(str::trim)
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_uppercase()))
+        .map(|line| line.split(|c: char| !c.is_alphanumeric()).next().unwrap());
+    let body = mapping_rs
+        .lines()
+        .skip_while(|&line| line != "impl ToChalk for Ty {")
+        .take_while(|&line| line != "}")
+        .collect::<Vec<_>>()
+        .join("\n");
+    let (to_chalk, from_chalk) = match body.find("fn from_chalk") {
+        Some(idx) => body.split_at(idx),
+        None => (body.as_str(), ""),
+    };
+
+    let mut res = Vec::new();
+    for variant in variants {
+        let pat = format!("Ty::{}", variant);
+        if !to_chalk.contains(&pat) {
+            res.push(format!("`{}` in `to_chalk`", pat));
+        }
+        if !from_chalk.contains(&pat) && !NOT_FROM_CHALK.contains(&variant) {
+            res.push(format!("`{}` in `from_chalk`", pat));
+        }
+    }
+    res
+}
+
+#[derive(Debug)]
+struct ToChalkImpl {
+    ty: String,
+    to_chalk: Completeness,
+    from_chalk: Completeness,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Completeness {
+    Complete,
+    MayPanic,
+    Unimplemented,
+}
+
+impl ToChalkImpl {
+    fn collect(text: &str) -> Vec<ToChalkImpl> {
+        let helpers = free_fns(text);
+        let mut res = Vec::new();
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            let ty = match line.strip_prefix("impl ToChalk for ") {
+                Some(rest) => rest.trim_end_matches('{').trim(),
+                None => continue,
+            };
+            let body = lines.by_ref().take_while(|&it| it != "}").collect::<Vec<_>>().join("\n");
+            let (to_chalk, from_chalk) = match body.find("fn from_chalk") {
+                Some(idx) => body.split_at(idx),
+                None => (body.as_str(), ""),
+            };
+            res.push(ToChalkImpl {
+                ty: ty.to_string(),
+                to_chalk: Completeness::of(to_chalk, &helpers),
+                from_chalk: Completeness::of(from_chalk, &helpers),
+            });
+        }
+        res
+    }
+}
+
+/// The bodies of the free functions in `text`, by name.
+fn free_fns(text: &str) -> HashMap<&str, String> {
+    let mut res = HashMap::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let name = match line.split_once("fn ") {
+            // only top-level functions, not methods
+            Some((vis, rest)) if vis.is_empty() || vis.starts_with("pub") => {
+                &rest[..rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len())]
+            }
+            _ => continue,
+        };
+        let body = lines.by_ref().take_while(|&it| it != "}").collect::<Vec<_>>().join("\n");
+        res.insert(name, body);
+    }
+    res
+}
+
+impl Completeness {
+    /// How complete the conversion in `body` is, following calls to the free
+    /// functions in `helpers`.
+    fn of(body: &str, helpers: &HashMap<&str, String>) -> Completeness {
+        let mut visited = vec![];
+        Completeness::of_rec(body, helpers, &mut visited)
+    }
+
+    fn of_rec<'a>(
+        body: &str,
+        helpers: &HashMap<&'a str, String>,
+        visited: &mut Vec<&'a str>,
+    ) -> Completeness {
+        let code = body
+            .lines()
+            .map(|line| match line.find("//") {
+                Some(idx) => &line[..idx],
+                None => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut res = if calls_macro(&code, "unimplemented") {
+            Completeness::Unimplemented
+        } else if PANICKING_MACROS.iter().any(|it| calls_macro(&code, it))
+            || code.contains(".expect(")
+            || code.contains(".unwrap()")
+        {
+            Completeness::MayPanic
+        } else {
+            Completeness::Complete
+        };
+        for (&name, helper) in helpers {
+            if !visited.contains(&name) && calls_fn(&code, name) {
+                visited.push(name);
+                res = res.max(Completeness::of_rec(helper, helpers, visited));
+            }
+        }
+        res
+    }
+}
+
+/// Macros which panic in release builds, too.
+const PANICKING_MACROS: &[&str] = &["panic", "unreachable", "assert", "assert_eq", "assert_ne"];
+
+fn calls_macro(code: &str, name: &str) -> bool {
+    follows_ident_boundary(code, &format!("{}!(", name))
+}
+
+/// Whether `code` calls the free function `name`, as opposed to a method or
+/// an associated function of the same name.
+fn calls_fn(code: &str, name: &str) -> bool {
+    code.match_indices(&format!("{}(", name)).any(|(idx, _)| {
+        !code[..idx].ends_with(|c: char| is_ident_char(c) || c == '.' || c == ':')
+            && !code[..idx].ends_with("fn ")
+    })
+}
+
+fn follows_ident_boundary(code: &str, pat: &str) -> bool {
+    code.match_indices(pat).any(|(idx, _)| !code[..idx].ends_with(is_ident_char))
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl fmt::Display for ToChalkImpl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let complete =
+            self.to_chalk == Completeness::Complete && self.from_chalk == Completeness::Complete;
+        write!(f, "- [{}] `{}`", if complete { "x" } else { " " }, self.ty)?;
+        for (name, completeness) in
+            [("to_chalk", self.to_chalk), ("from_chalk", self.from_chalk)].iter()
+        {
+            match completeness {
+                Completeness::Complete => (),
+                Completeness::Unimplemented => write!(f, ", `{}` is partly unimplemented", name)?,
+                Completeness::MayPanic => write!(f, ", `{}` may panic", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_incomplete_conversions() {
+        let text = r#"
+impl ToChalk for Complete {
+    fn to_chalk(self, db: &dyn HirDatabase) -> Chalk {
+        Chalk
+    }
+
+    fn from_chalk(db: &dyn HirDatabase, chalk: Chalk) -> Self {
+        Complete
+    }
+}
+
+impl ToChalk for Partial {
+    fn to_chalk(self, db: &dyn HirDatabase) -> Chalk {
+        panic!("no")
+    }
+
+    fn from_chalk(db: &dyn HirDatabase, chalk: Chalk) -> Self {
+        unimplemented!()
+    }
+}
+
+impl ToChalk for Delegating {
+    fn to_chalk(self, db: &dyn HirDatabase) -> Chalk {
+        // `.helper()` is a method, not the free function below
+        self.helper().chalk()
+    }
+
+    fn from_chalk(db: &dyn HirDatabase, chalk: Chalk) -> Self {
+        helper(db, chalk)
+    }
+}
+
+pub(super) fn helper(db: &dyn HirDatabase, chalk: Chalk) -> Delegating {
+    nested_helper(chalk)
+}
+
+fn nested_helper(chalk: Chalk) -> Delegating {
+    chalk.delegating().expect("not delegating")
+}
+"#;
+        let impls =
+            ToChalkImpl::collect(text).into_iter().map(|it| it.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            impls,
+            vec![
+                "- [x] `Complete`",
+                "- [ ] `Partial`, `to_chalk` may panic, `from_chalk` is partly unimplemented",
+                "- [ ] `Delegating`, `from_chalk` may panic",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_ty_variants_missing_a_direction() {
+        let lib_rs = r#"
+pub enum Ty {
+    /// A scalar.
+    Scalar(Scalar),
+
+    Str,
+    InferenceVar(InferenceVar, TyVariableKind),
+    Unknown,
+}
+"#;
+        let mapping_rs = r#"
+impl ToChalk for Ty {
+    fn to_chalk(self, db: &dyn HirDatabase) -> chalk_ir::Ty<Interner> {
+        match self {
+            Ty::Scalar(scalar) => scalar,
+            Ty::Str => str,
+            Ty::InferenceVar(..) => panic!(),
+            Ty::Unknown => error,
+        }
+    }
+
+    fn from_chalk(db: &dyn HirDatabase, chalk: chalk_ir::Ty<Interner>) -> Self {
+        match chalk {
+            Scalar(scalar) => Ty::Scalar(scalar),
+            Error => Ty::Unknown,
+        }
+    }
+}
+"#;
+        assert_eq!(unconverted_ty_variants(lib_rs, mapping_rs), vec!["`Ty::Str` in `from_chalk`"]);
+    }
+}
@@ -0,0 +1,136 @@
+//! Generates a round-trip test for each variant of `chalk_ir::TyKind`, read
+//! from the source of the `chalk-ir` version we depend on, so that a Chalk
+//! upgrade adding a variant shows up as a new test.
+//!
+//! Tests of the kinds `Ty::from_chalk` doesn't handle yet (its arm is
+//! `unimplemented!()`) are ignored, to be enabled once they are supported.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, format_err};
+use xshell::{cmd, pushd};
+
+use crate::{
+    codegen::{self, reformat_with, Mode, Outcomes},
+    project_root, Result,
+};
+
+const MAPPING_RS: &str = "crates/hir_ty/src/traits/chalk/mapping.rs";
+const DST: &str = "crates/hir_ty/src/traits/chalk/mapping/tests/ty_kinds.rs";
+
+pub(crate) fn generate_ty_kind_tests(mode: Mode) -> Result<Outcomes> {
+    let chalk_ir_lib_rs = xshell::read_file(chalk_ir_dir()?.join("src/lib.rs"))?;
+    let mapping_rs = xshell::read_file(project_root().join(MAPPING_RS))?;
+    let kinds = ty_kinds(&chalk_ir_lib_rs)?;
+    let unimplemented = unimplemented_ty_kinds(&mapping_rs);
+    let text = tests_text(&kinds, &unimplemented);
+    let contents = reformat_with("TyKind round-trip tests", &text, &[("fn_single_line", "false")])?;
+    let dst = project_root().join(DST);
+    let outcome = codegen::update(&dst, &contents, mode)?;
+    Ok(vec![(dst, outcome)])
+}
+
+/// The directory of the `chalk-ir` sources `hir_ty` is built with.
+fn chalk_ir_dir() -> Result<PathBuf> {
+    let _d = pushd(project_root())?;
+    let metadata = cmd!("cargo metadata --format-version 1").read()?;
+    // avoid a JSON parser for the one path we need
+    let manifest = metadata
+        .split('"')
+        .find(|it| it.ends_with("Cargo.toml") && it.contains("chalk-ir-"))
+        .ok_or_else(|| format_err!("`chalk-ir` not found in `cargo metadata`"))?;
+    Ok(PathBuf::from(manifest).parent().unwrap().to_path_buf())
+}
+
+/// The variants of `pub enum TyKind` in `lib_rs`.
+fn ty_kinds(lib_rs: &str) -> Result<Vec<String>> {
+    let mut lines = lib_rs.lines().skip_while(|line| !line.starts_with("pub enum TyKind<"));
+    if lines.next().is_none() {
+        bail!("`pub enum TyKind` not found in chalk-ir");
+    }
+    let res = lines
+        .take_while(|&line| line != "}")
+        .map(str::trim)
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_uppercase()))
+        .map(|line| line.split(|c: char| !c.is_alphanumeric()).next().unwrap().to_string())
+        .collect();
+    Ok(res)
+}
+
+/// The `TyKind`s whose arm in `mapping_rs` is `unimplemented!()`.
+fn unimplemented_ty_kinds(mapping_rs: &str) -> Vec<String> {
+    mapping_rs
+        .lines()
+        .filter(|line| line.contains("=> unimplemented!()"))
+        .filter_map(|line| line.trim().strip_prefix("chalk_ir::TyKind::"))
+        .map(|rest| rest.split(|c: char| !c.is_alphanumeric()).next().unwrap().to_string())
+        .collect()
+}
+
+fn tests_text(kinds: &[String], unimplemented: &[String]) -> String {
+    let mut buf = String::from("use super::check_ty_kind_round_trips;\n");
+    for kind in kinds {
+        buf.push_str("\n#[test]\n");
+        if unimplemented.contains(kind) {
+            buf.push_str(&format!(
+                "#[ignore = \"`from_chalk` doesn't handle `TyKind::{}` yet\"]\n",
+                kind
+            ));
+        }
+        buf.push_str(&format!(
+            "fn {}_round_trips() {{\n    check_ty_kind_round_trips({:?});\n}}\n",
+            to_snake_case(kind),
+            kind
+        ));
+    }
+    buf
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut res = String::new();
+    for (idx, c) in name.char_indices() {
+        if c.is_ascii_uppercase() && idx > 0 {
+            res.push('_');
+        }
+        res.push(c.to_ascii_lowercase());
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_ty_kinds_and_unimplemented_arms() {
+        let lib_rs = r#"
+pub enum TyKind<I: Interner> {
+    /// Abstract data types.
+    Adt(AdtId<I>, Substitution<I>),
+
+    Str,
+    GeneratorWitness(GeneratorId<I>, Substitution<I>),
+}
+"#;
+        let mapping_rs = r#"
+            chalk_ir::TyKind::Str => Ty::Str,
+            chalk_ir::TyKind::GeneratorWitness(_, _) => unimplemented!(), // FIXME
+"#;
+        let kinds = ty_kinds(lib_rs).unwrap();
+        assert_eq!(kinds, ["Adt", "Str", "GeneratorWitness"]);
+        let unimplemented = unimplemented_ty_kinds(mapping_rs);
+        assert_eq!(unimplemented, ["GeneratorWitness"]);
+
+        let text = tests_text(&kinds, &unimplemented);
+        assert!(text.contains("fn adt_round_trips() {\n    check_ty_kind_round_trips(\"Adt\");"));
+        assert!(text.contains(
+            "#[test]\n#[ignore = \"`from_chalk` doesn't handle `TyKind::GeneratorWitness` yet\"]\n\
+             fn generator_witness_round_trips()"
+        ));
+    }
+
+    #[test]
+    fn missing_ty_kind_enum_is_an_error() {
+        assert!(ty_kinds("pub enum Ty {\n}\n").is_err());
+    }
+}
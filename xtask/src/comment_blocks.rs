@@ -0,0 +1,124 @@
+//! Blocks of `// ` comments, which several xtasks use as a lightweight markup
+//! in the source code, e.g. `// test name` for parser tests or
+//! `// Feature: Name` for the user manual.
+
+use std::mem;
+
+/// Returns the contents of every block of consecutive `// ` comments in
+/// `text`, one line per element.
+pub(crate) fn extract_comment_blocks(text: &str) -> Vec<Vec<String>> {
+    do_extract_comment_blocks(text, false).into_iter().map(|(_line, block)| block).collect()
+}
+
+/// Returns the blocks of `// ` comments in `text` whose first line is
+/// `<tag>: <id>`. Unlike [`extract_comment_blocks`], a bare `//` line doesn't
+/// end the block. For example, with the `Feature` tag,
+///
+/// ```text
+/// // Feature: Frobnicate
+/// //
+/// // Frobnicates the selection.
+/// ```
+///
+/// is a block with id `Frobnicate` and contents `["", "Frobnicates the selection."]`.
+pub(crate) fn extract_comment_blocks_with_empty_lines(tag: &str, text: &str) -> Vec<CommentBlock> {
+    assert!(tag.starts_with(char::is_uppercase));
+    let tag = format!("{}:", tag);
+    let mut res = Vec::new();
+    for (line, mut block) in do_extract_comment_blocks(text, true) {
+        let first = block.remove(0);
+        if first.starts_with(&tag) {
+            let id = first[tag.len()..].trim().to_string();
+            let block = CommentBlock { id, line, contents: block };
+            res.push(block);
+        }
+    }
+    res
+}
+
+pub(crate) struct CommentBlock {
+    pub(crate) id: String,
+    /// The line of the tag, starting from 1.
+    pub(crate) line: usize,
+    pub(crate) contents: Vec<String>,
+}
+
+fn do_extract_comment_blocks(
+    text: &str,
+    allow_blocks_with_empty_lines: bool,
+) -> Vec<(usize, Vec<String>)> {
+    let mut res = Vec::new();
+
+    let prefix = "// ";
+    // `lines` only strips a `\r` that's followed by `\n`, so a file ending in
+    // one would leave it in the last line.
+    let lines = text.lines().map(|line| line.trim_start().trim_end_matches('\r'));
+
+    let mut block = (0, vec![]);
+    for (line_num, line) in lines.enumerate() {
+        if line == "//" && allow_blocks_with_empty_lines {
+            block.1.push(String::new());
+            continue;
+        }
+
+        // Doc comments (including our own `//!` preamble) are never part of a
+        // block, even though they start with `//`.
+        let is_doc_comment = line.starts_with("//!") || line.starts_with("///");
+        let is_comment = line.starts_with(prefix) && !is_doc_comment;
+        if is_comment {
+            block.1.push(line[prefix.len()..].to_string());
+        } else {
+            if !block.1.is_empty() {
+                res.push(mem::take(&mut block));
+            }
+            block.0 = line_num + 2;
+        }
+    }
+    if !block.1.is_empty() {
+        res.push(block)
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doc_comments_are_not_comment_blocks() {
+        let text = "//! Generated file, do not edit by hand
+// Feature: Foo
+//
+// Does foo.
+/// Docs for `foo`.
+//! Module docs.
+fn foo() {}
+";
+        let blocks = extract_comment_blocks_with_empty_lines("Feature", text);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].id, "Foo");
+        assert_eq!(blocks[0].line, 2);
+        assert_eq!(blocks[0].contents, vec!["".to_string(), "Does foo.".to_string()]);
+        assert_eq!(
+            extract_comment_blocks(text),
+            vec![vec!["Feature: Foo".to_string()], vec!["Does foo.".to_string()]]
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings_are_stripped() {
+        let text = "// Feature: Foo\r\n//\r\n// Does foo.\r\nfn foo() {}\r\n// Trailing.\r";
+        let blocks = extract_comment_blocks_with_empty_lines("Feature", text);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].id, "Foo");
+        assert_eq!(blocks[0].contents, vec!["".to_string(), "Does foo.".to_string()]);
+        assert_eq!(
+            extract_comment_blocks(text),
+            vec![
+                vec!["Feature: Foo".to_string()],
+                vec!["Does foo.".to_string()],
+                vec!["Trailing.".to_string()]
+            ]
+        );
+    }
+}
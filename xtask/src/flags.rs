@@ -24,7 +24,16 @@ xflags::args_parser! {
         }
 
         cmd codegen {
-            optional --features
+            /// Don't regenerate lint completions, which downloads the lint lists.
+            optional --skip-lint-completions
+            /// List out-of-date generated files instead of overwriting them.
+            optional --check
+            /// Pass `false` to run the other generators after one fails, reporting all failures at the end.
+            optional --fail-fast value: bool
+            /// Only run the generators whose inputs changed since this git revision.
+            optional --since rev: String
+            /// Only regenerate the tests of the assist with this id, leaving the other tests alone.
+            optional --assist id: String
         }
 
         cmd lint {}
@@ -90,7 +99,11 @@ pub struct Install {
 
 #[derive(Debug)]
 pub struct Codegen {
-    pub features: bool,
+    pub skip_lint_completions: bool,
+    pub check: bool,
+    pub fail_fast: Option<bool>,
+    pub since: Option<String>,
+    pub assist: Option<String>,
 }
 
 #[derive(Debug)]
@@ -10,6 +10,7 @@
 mod flags;
 
 mod codegen;
+mod comment_blocks;
 mod ast_src;
 #[cfg(test)]
 mod tidy;
@@ -119,7 +120,7 @@ fn run_rustfmt(mode: Mode) -> Result<()> {
     ensure_rustfmt()?;
     let check = match mode {
         Mode::Overwrite => &[][..],
-        Mode::Verify => &["--", "--check"],
+        Mode::Verify | Mode::Check => &["--", "--check"],
     };
     cmd!("cargo fmt {check...}").run()?;
     Ok(())
@@ -32,6 +32,27 @@ fn generated_assists_are_fresh() {
     }
 }
 
+#[test]
+fn generated_to_chalk_audit_is_fresh() {
+    if let Err(error) = codegen::generate_to_chalk_audit(Mode::Verify) {
+        panic!("{}. Please update it by running `cargo xtask codegen`", error);
+    }
+}
+
+#[test]
+fn generated_obligation_docs_are_fresh() {
+    if let Err(error) = codegen::generate_obligation_docs(Mode::Verify) {
+        panic!("{}. Please update them by running `cargo xtask codegen`", error);
+    }
+}
+
+#[test]
+fn generated_ty_kind_tests_are_fresh() {
+    if let Err(error) = codegen::generate_ty_kind_tests(Mode::Verify) {
+        panic!("{}. Please update them by running `cargo xtask codegen`", error);
+    }
+}
+
 #[test]
 fn check_code_formatting() {
     if let Err(error) = run_rustfmt(Mode::Verify) {